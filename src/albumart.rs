@@ -0,0 +1,267 @@
+//! The module defines album art data structures and on-disk album art cache
+//!
+//! Album art can be either embedded into song file (use
+//! [`readpicture()`](crate::Client::readpicture) to get it) or stored as a cover file
+//! in the song's directory (use [`albumart()`](crate::Client::albumart) to get it).
+//!
+//! As the same cover is usually shared by all songs of an album, [`ArtCache`] stores
+//! downloaded images on disk, keyed by song's directory, so that they are transferred
+//! over network only once.
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::idle::Subsystem;
+use crate::song::Song;
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// Album art image
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AlbumArt {
+    /// raw image data
+    pub data: Vec<u8>,
+    /// image MIME type (like `image/jpeg`), if reported by server
+    pub mime: Option<String>,
+}
+
+/// Album art metadata, returned when album art is streamed into a writer
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AlbumArtInfo {
+    /// total image size in bytes
+    pub size: usize,
+    /// image MIME type (like `image/jpeg`), if reported by server
+    pub mime: Option<String>,
+}
+
+// Album art cache {{{
+#[derive(Debug, Clone)]
+struct Entry {
+    directory: String,
+    last_mod: Option<String>,
+    mime: Option<String>,
+    found: bool,
+    size: u64,
+    used: u64,
+    // database was updated since the entry was checked against the song
+    stale: bool,
+}
+
+impl Entry {
+    fn to_meta(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n",
+            self.directory,
+            self.last_mod.as_deref().unwrap_or(""),
+            self.mime.as_deref().unwrap_or(""),
+            self.found as u8
+        )
+    }
+
+    fn from_meta(meta: &str, size: u64, used: u64) -> Option<Entry> {
+        let mut lines = meta.lines();
+        let non_empty = |v: &str| if v.is_empty() { None } else { Some(v.to_owned()) };
+        Some(Entry {
+            directory: lines.next()?.to_owned(),
+            last_mod: lines.next().and_then(non_empty),
+            mime: lines.next().and_then(non_empty),
+            found: lines.next()? == "1",
+            size,
+            used,
+            stale: false,
+        })
+    }
+}
+
+/// On-disk album art cache
+///
+/// Images are stored in a cache directory, keyed by song's parent directory,
+/// and refetched if the song was modified after the image was cached (according
+/// to its `Last-Modified` field). Songs without album art are cached as well.
+///
+/// Total size of cached images is bounded, least recently used images
+/// are evicted first.
+///
+/// Cache doesn't watch for database changes itself, pass events from
+/// [`idle`](crate::Idle::idle) to [`update()`](ArtCache::update) to revalidate
+/// cached images after database updates.
+#[derive(Debug)]
+pub struct ArtCache {
+    dir: PathBuf,
+    capacity: u64,
+    size: u64,
+    tick: u64,
+    entries: HashMap<String, Entry>,
+}
+
+impl ArtCache {
+    /// Open (or create) cache in a given directory, limiting its total size to `capacity` bytes
+    pub fn open<P: Into<PathBuf>>(dir: P, capacity: u64) -> io::Result<ArtCache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut found = Vec::new();
+        let mut keys = HashSet::new();
+        for item in fs::read_dir(&dir)? {
+            let path = item?.path();
+            let key = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(key) if path.extension() == Some("bin".as_ref()) || path.extension() == Some("meta".as_ref()) => key.to_owned(),
+                _ => continue,
+            };
+            if path.extension() == Some("meta".as_ref()) {
+                if let (Ok(data), Ok(meta)) = (fs::metadata(path.with_extension("bin")), fs::read_to_string(&path)) {
+                    found.push((fs::metadata(&path)?.modified()?, key.clone(), meta, data.len()));
+                }
+            }
+            keys.insert(key);
+        }
+        // Restore usage order from access times, metadata files are rewritten on every access
+        found.sort();
+
+        let mut cache = ArtCache { dir, capacity, size: 0, tick: 0, entries: HashMap::new() };
+        for (_, key, meta, size) in found {
+            cache.tick += 1;
+            if let Some(entry) = Entry::from_meta(&meta, size, cache.tick) {
+                cache.size += entry.size;
+                cache.entries.insert(key, entry);
+            }
+        }
+        // Remove broken entries (like images without metadata), they would never be evicted otherwise
+        for key in keys.iter().filter(|key| !cache.entries.contains_key(*key)) {
+            remove_entry(&cache.dir, key)?;
+        }
+        cache.evict()?;
+        Ok(cache)
+    }
+
+    /// Total size of cached images in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get cover art for a song, either from cache or from server
+    ///
+    /// Cover art is requested with [`cover_art()`](Client::cover_art),
+    /// so embedded pictures take precedence over cover files.
+    pub fn get<S: Read + Write>(&mut self, client: &mut Client<S>, song: &Song) -> Result<Option<AlbumArt>> {
+        let directory = match song.file.rfind('/') {
+            Some(pos) => &song.file[..pos],
+            None => "",
+        };
+        let key = cache_key(directory);
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            let valid = match song.last_mod {
+                // After database update, directories without art are checked again, as cover files may have been added
+                Some(_) => song.last_mod <= entry.last_mod && (entry.found || !entry.stale),
+                None => !entry.stale,
+            };
+            if entry.directory == directory && valid {
+                self.tick += 1;
+                entry.used = self.tick;
+                entry.stale = false;
+                // Touch metadata file to keep usage order across restarts
+                File::create(self.dir.join(&key).with_extension("meta"))?.write_all(entry.to_meta().as_bytes())?;
+                if !entry.found {
+                    return Ok(None);
+                }
+
+                let mut data = Vec::new();
+                File::open(self.dir.join(&key).with_extension("bin"))?.read_to_end(&mut data)?;
+                return Ok(Some(AlbumArt { data, mime: entry.mime.clone() }));
+            }
+        }
+
+        let art = client.cover_art(song)?;
+        self.insert(key, directory, song.last_mod.clone(), art.as_ref())?;
+        Ok(art)
+    }
+
+    /// Mark cached images for revalidation if database was updated
+    ///
+    /// Images are kept on disk, and on next [`get()`](ArtCache::get) they are
+    /// reused if the song wasn't modified since, or refetched otherwise.
+    ///
+    /// Call it with subsystems returned from [`wait()`](crate::Idle::wait) or
+    /// [`IdleGuard::get()`](crate::idle::IdleGuard::get).
+    pub fn update(&mut self, subsystems: &[Subsystem]) {
+        if subsystems.contains(&Subsystem::Database) {
+            for entry in self.entries.values_mut() {
+                entry.stale = true;
+            }
+        }
+    }
+
+    /// Drop all cached images
+    pub fn clear(&mut self) -> io::Result<()> {
+        for key in self.entries.keys() {
+            remove_entry(&self.dir, key)?;
+        }
+        self.entries.clear();
+        self.size = 0;
+        Ok(())
+    }
+
+    fn insert(&mut self, key: String, directory: &str, last_mod: Option<String>, art: Option<&AlbumArt>) -> io::Result<()> {
+        if let Some(old) = self.entries.remove(&key) {
+            self.size -= old.size;
+        }
+
+        let data = art.map(|a| &*a.data).unwrap_or_default();
+        if data.len() as u64 > self.capacity {
+            return remove_entry(&self.dir, &key);
+        }
+
+        self.tick += 1;
+        let entry = Entry {
+            directory: directory.to_owned(),
+            last_mod,
+            mime: art.and_then(|a| a.mime.clone()),
+            found: art.is_some(),
+            size: data.len() as u64,
+            used: self.tick,
+            stale: false,
+        };
+        File::create(self.dir.join(&key).with_extension("bin"))?.write_all(data)?;
+        File::create(self.dir.join(&key).with_extension("meta"))?.write_all(entry.to_meta().as_bytes())?;
+
+        self.size += entry.size;
+        self.entries.insert(key, entry);
+        self.evict()
+    }
+
+    fn evict(&mut self) -> io::Result<()> {
+        while self.size > self.capacity {
+            let key = match self.entries.iter().min_by_key(|(_, e)| e.used) {
+                Some((key, _)) => key.clone(),
+                None => break,
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.size -= entry.size;
+            }
+            remove_entry(&self.dir, &key)?;
+        }
+        Ok(())
+    }
+}
+
+fn remove_entry(dir: &std::path::Path, key: &str) -> io::Result<()> {
+    for ext in ["bin", "meta"] {
+        match fs::remove_file(dir.join(key).with_extension(ext)) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+// FNV-1a hash, stable across Rust versions, unlike std's `DefaultHasher`
+fn cache_key(directory: &str) -> String {
+    let hash = directory.bytes().fold(0xcbf29ce484222325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+// }}}
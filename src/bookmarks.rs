@@ -0,0 +1,170 @@
+//! The module defines resume-position bookmarks for long files, like audiobooks and podcasts
+//!
+//! MPD forgets playback position as soon as another song is played.
+//! [`Bookmarks`] keeps track of player events, saves playback position of long
+//! files into an `elapsed` song sticker (the same one myMPD uses), and seeks back
+//! to the saved position when the file is played again.
+//!
+//! Bookmarks don't own a connection, so they can be used with an existing idle loop:
+//!
+//! ```rust,no_run
+//! use mpd::{Bookmarks, Client, Idle, Subsystem};
+//!
+//! let mut events = Client::connect("127.0.0.1:6600").unwrap();
+//! let mut conn = Client::connect("127.0.0.1:6600").unwrap();
+//! let mut bookmarks = Bookmarks::new();
+//! loop {
+//!     let subsystems = events.wait(&[Subsystem::Player]).unwrap();
+//!     bookmarks.update(&mut conn, &subsystems).unwrap();
+//! }
+//! ```
+
+use crate::client::Client;
+use crate::error::{Error, ErrorCode, Result, ServerError};
+use crate::idle::Subsystem;
+use crate::song::Id;
+use crate::status::State;
+use crate::sticker::StickerType;
+
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+/// Sticker name for saved playback position, in seconds
+pub const ELAPSED: &str = "elapsed";
+
+#[derive(Debug, Clone)]
+struct Playing {
+    file: String,
+    duration: Duration,
+    elapsed: Duration,
+    at: Instant,
+    playing: bool,
+}
+
+impl Playing {
+    // Estimated playback position at this moment
+    fn position(&self) -> Duration {
+        if self.playing {
+            (self.elapsed + self.at.elapsed()).min(self.duration)
+        } else {
+            self.elapsed
+        }
+    }
+}
+
+/// Resume-position bookmarks
+#[derive(Debug, Clone)]
+pub struct Bookmarks {
+    min_duration: Duration,
+    end_margin: Duration,
+    playing: Option<Playing>,
+}
+
+impl Default for Bookmarks {
+    fn default() -> Bookmarks {
+        Bookmarks { min_duration: Duration::from_secs(20 * 60), end_margin: Duration::from_secs(30), playing: None }
+    }
+}
+
+impl Bookmarks {
+    /// Create bookmarks for files longer than 20 minutes, cleared in the last 30 seconds of a file
+    pub fn new() -> Bookmarks {
+        Bookmarks::default()
+    }
+
+    /// Keep bookmarks only for files at least this long
+    pub fn min_duration(&mut self, duration: Duration) -> &mut Bookmarks {
+        self.min_duration = duration;
+        self
+    }
+
+    /// Clear bookmark if a file was stopped this close to its end
+    pub fn end_margin(&mut self, margin: Duration) -> &mut Bookmarks {
+        self.end_margin = margin;
+        self
+    }
+
+    /// Get saved playback position for a file
+    pub fn bookmark<S: Read + Write>(&self, client: &mut Client<S>, file: &str) -> Result<Option<Duration>> {
+        match client.sticker::<_, f64>(StickerType::Song, file, ELAPSED) {
+            Ok(secs) => Ok(Duration::try_from_secs_f64(secs).ok()),
+            Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Remove saved playback position for a file
+    pub fn clear<S: Read + Write>(&self, client: &mut Client<S>, file: &str) -> Result<()> {
+        match client.delete_sticker(StickerType::Song, file, ELAPSED) {
+            Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Handle idle events
+    ///
+    /// Call it with subsystems returned from [`wait()`](crate::Idle::wait) or
+    /// [`IdleGuard::get()`](crate::idle::IdleGuard::get), it does nothing
+    /// unless there is a [`Player`](Subsystem::Player) event.
+    pub fn update<S: Read + Write>(&mut self, client: &mut Client<S>, subsystems: &[Subsystem]) -> Result<()> {
+        if subsystems.contains(&Subsystem::Player) {
+            self.poll(client)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Save playback position of current file and resume a file which just started playing
+    ///
+    /// This is what [`update()`](Bookmarks::update) does on player events, but it also can be
+    /// called periodically to keep saved position up to date.
+    pub fn poll<S: Read + Write>(&mut self, client: &mut Client<S>) -> Result<()> {
+        let status = client.status()?;
+        let current = match (status.state, status.song, status.duration) {
+            (State::Stop, _, _) => None,
+            (_, Some(place), Some(duration)) => client.playlistid(place.id)?.map(|song| (song.file, place.id, duration)),
+            _ => None,
+        };
+
+        let previous = self.playing.take();
+        if let Some(ref prev) = previous {
+            if current.as_ref().map(|c| &c.0) != Some(&prev.file) {
+                self.save(client, &prev.file, prev.duration, prev.position())?;
+            }
+        }
+
+        if let Some((file, id, duration)) = current {
+            let mut elapsed = status.elapsed.unwrap_or_default();
+            let started = previous.map(|p| p.file != file).unwrap_or(true);
+            if started && duration >= self.min_duration {
+                elapsed = self.resume(client, &file, id, elapsed)?;
+            } else {
+                self.save(client, &file, duration, elapsed)?;
+            }
+
+            self.playing = Some(Playing { file, duration, elapsed, at: Instant::now(), playing: status.state == State::Play });
+        }
+        Ok(())
+    }
+
+    // Seek to saved position, if the file just started playing from the beginning
+    fn resume<S: Read + Write>(&self, client: &mut Client<S>, file: &str, id: Id, elapsed: Duration) -> Result<Duration> {
+        if elapsed > Duration::from_secs(5) {
+            return Ok(elapsed);
+        }
+        match self.bookmark(client, file)? {
+            Some(pos) => client.seek(id, pos).map(|_| pos),
+            None => Ok(elapsed),
+        }
+    }
+
+    fn save<S: Read + Write>(&self, client: &mut Client<S>, file: &str, duration: Duration, elapsed: Duration) -> Result<()> {
+        if duration < self.min_duration || elapsed.is_zero() {
+            Ok(())
+        } else if elapsed + self.end_margin >= duration {
+            self.clear(client, file)
+        } else {
+            client.set_sticker(StickerType::Song, file, ELAPSED, elapsed.as_secs())
+        }
+    }
+}
@@ -6,25 +6,31 @@
 
 use bufstream::BufStream;
 
+use crate::albumart::{AlbumArt, AlbumArtInfo};
 use crate::convert::*;
-use crate::error::{Error, ParseError, ProtoError, Result};
+use crate::error::{Error, ErrorCode, ParseError, ProtoError, Result, ServerError};
 use crate::message::{Channel, Message};
 use crate::mount::{Mount, Neighbor};
 use crate::output::Output;
 use crate::playlist::Playlist;
 use crate::plugin::Plugin;
 use crate::proto::*;
+use crate::reply::Reply;
 use crate::search::{Query, Term, Window};
 use crate::song::{Id, Song};
 use crate::stats::Stats;
-use crate::status::{ReplayGain, Status};
-use crate::sticker::Sticker;
+use crate::status::{ConsumeMode, ReplayGain, SingleMode, State, Status};
+use crate::sticker::{Sticker, StickerEntry, StickerOp, StickerQuery, StickerType};
 use crate::version::Version;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::convert::From;
-use std::io::{BufRead, Lines, Read, Write};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
 use std::net::{TcpStream, ToSocketAddrs};
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::Duration;
 
 // Client {{{
 
@@ -95,6 +101,27 @@ impl<S: Read + Write> Client<S> {
         self.run_command("setvol", volume).and_then(|_| self.expect_ok())
     }
 
+    /// Change volume by a given (positive or negative) amount
+    pub fn volume_change(&mut self, delta: i8) -> Result<()> {
+        self.run_command("volume", delta).and_then(|_| self.expect_ok())
+    }
+
+    /// Get current volume (requires MPD 0.23)
+    ///
+    /// Returns `None` if there is no mixer.
+    pub fn getvol(&mut self) -> Result<Option<i8>> {
+        self.run_command("getvol", ())?;
+
+        let mut volume = None;
+        for pair in self.read_pairs() {
+            let (key, value) = pair?;
+            if key == "volume" {
+                volume = Some(value.parse()?).filter(|&volume: &i8| volume >= 0);
+            }
+        }
+        Ok(volume)
+    }
+
     /// Set repeat state
     pub fn repeat(&mut self, value: bool) -> Result<()> {
         self.run_command("repeat", value as u8).and_then(|_| self.expect_ok())
@@ -106,13 +133,17 @@ impl<S: Read + Write> Client<S> {
     }
 
     /// Set single state
-    pub fn single(&mut self, value: bool) -> Result<()> {
-        self.run_command("single", value as u8).and_then(|_| self.expect_ok())
+    ///
+    /// Accepts either `bool` or [`SingleMode`] (to set `oneshot` mode).
+    pub fn single<T: Into<SingleMode>>(&mut self, value: T) -> Result<()> {
+        self.run_command("single", value.into()).and_then(|_| self.expect_ok())
     }
 
     /// Set consume state
-    pub fn consume(&mut self, value: bool) -> Result<()> {
-        self.run_command("consume", value as u8).and_then(|_| self.expect_ok())
+    ///
+    /// Accepts either `bool` or [`ConsumeMode`] (to set `oneshot` mode).
+    pub fn consume<T: Into<ConsumeMode>>(&mut self, value: T) -> Result<()> {
+        self.run_command("consume", value.into()).and_then(|_| self.expect_ok())
     }
 
     /// Set crossfade time in seconds
@@ -134,6 +165,13 @@ impl<S: Read + Write> Client<S> {
     pub fn replaygain(&mut self, gain: ReplayGain) -> Result<()> {
         self.run_command("replay_gain_mode", gain).and_then(|_| self.expect_ok())
     }
+
+    /// Get replay gain mode
+    ///
+    /// Unlike [`Status::replaygain`], this works with all servers, as it uses the dedicated command.
+    pub fn replaygain_status(&mut self) -> Result<ReplayGain> {
+        self.run_command("replay_gain_status", ()).and_then(|_| self.read_field("replay_gain_mode"))
+    }
     // }}}
 
     // Playback control {{{
@@ -181,8 +219,11 @@ impl<S: Read + Write> Client<S> {
     }
 
     /// Seek to a given place (in seconds) in the current song
-    pub fn rewind<T: ToSeconds>(&mut self, pos: T) -> Result<()> {
-        self.run_command("seekcur", pos.to_seconds()).and_then(|_| self.expect_ok())
+    ///
+    /// Use [`SeekTarget`](crate::SeekTarget) to seek relative to current position,
+    /// e.g. `SeekTarget::Forward(Duration::from_secs(30))` to skip 30 seconds ahead.
+    pub fn rewind<T: ToSeekTarget>(&mut self, pos: T) -> Result<()> {
+        self.run_command("seekcur", pos.to_seek_target()).and_then(|_| self.expect_ok())
     }
     // }}}
 
@@ -190,36 +231,63 @@ impl<S: Read + Write> Client<S> {
     /// List given song or range of songs in a play queue
     pub fn songs<T: ToQueueRangeOrPlace>(&mut self, pos: T) -> Result<Vec<Song>> {
         let command = if T::is_id() { "playlistid" } else { "playlistinfo" };
-        self.run_command(command, pos.to_range()).and_then(|_| self.read_structs("file"))
+        self.run_command(command, pos.to_range()).and_then(|_| self.read_songs())
+    }
+
+    /// List songs in given queue ranges and with given ids, in a single request
+    ///
+    /// Songs from ranges come first, in range order, then songs for ids.
+    pub fn queue_songs(&mut self, ranges: &[Range<u32>], ids: &[Id]) -> Result<Vec<Song>> {
+        if ranges.is_empty() && ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.run_command("command_list_begin", ())?;
+        for range in ranges {
+            self.run_command("playlistinfo", ToQueueRange::to_range(range.clone()))?;
+        }
+        for id in ids {
+            self.run_command("playlistid", *id)?;
+        }
+        self.run_command("command_list_end", ())?;
+        self.read_songs()
+    }
+
+    /// Find songs in a queue exactly matching a query
+    ///
+    /// Use [`Query::priority_at_least()`] to filter by song priority.
+    pub fn queue_find(&mut self, query: &Query) -> Result<Vec<Song>> {
+        self.run_command("playlistfind", query).and_then(|_| self.read_songs())
+    }
+
+    /// Search songs in a queue, like [`queue_find()`](Client::queue_find), but case insensitive
+    pub fn queue_search(&mut self, query: &Query) -> Result<Vec<Song>> {
+        self.run_command("playlistsearch", query).and_then(|_| self.read_songs())
     }
 
     /// List all songs in a play queue
     pub fn queue(&mut self) -> Result<Vec<Song>> {
-        self.run_command("playlistinfo", ()).and_then(|_| self.read_structs("file"))
+        self.run_command("playlistinfo", ()).and_then(|_| self.read_songs())
     }
 
     /// Lists all songs in the database
     pub fn listall(&mut self) -> Result<Vec<Song>> {
-        self.run_command("listall", ()).and_then(|_| self.read_structs("file"))
+        self.run_command("listall", ()).and_then(|_| self.read_songs())
     }
 
     /// Lists all songs in the database with metadata
     pub fn listallinfo(&mut self) -> Result<Vec<Song>> {
-        self.run_command("listallinfo", ()).and_then(|_| self.read_structs("file"))
+        self.run_command("listallinfo", ()).and_then(|_| self.read_songs())
     }
 
     /// Get current playing song
     pub fn currentsong(&mut self) -> Result<Option<Song>> {
-        self.run_command("currentsong", ())
-            .and_then(|_| self.read_struct::<Song>())
-            .map(|s| if s.place.is_none() { None } else { Some(s) })
+        self.run_command("currentsong", ()).and_then(|_| self.read_songs()).map(|s| s.into_iter().find(|s| s.place.is_some()))
     }
 
     /// gets the song wrt to songid in the playlist
     pub fn playlistid(&mut self, id: Id) -> Result<Option<Song>> {
-        self.run_command("playlistid", id)
-            .and_then(|_| self.read_struct::<Song>())
-            .map(|s| if s.place.is_none() { None } else { Some(s) })
+        self.run_command("playlistid", id).and_then(|_| self.read_songs()).map(|s| s.into_iter().find(|s| s.place.is_some()))
     }
 
     /// Clear current queue
@@ -229,7 +297,26 @@ impl<S: Read + Write> Client<S> {
 
     /// List all changes in a queue since given version
     pub fn changes(&mut self, version: u32) -> Result<Vec<Song>> {
-        self.run_command("plchanges", version).and_then(|_| self.read_structs("file"))
+        self.run_command("plchanges", version).and_then(|_| self.read_songs())
+    }
+
+    /// List positions and ids of all songs changed in a queue since given version
+    ///
+    /// This is a lighter version of [`changes()`](Client::changes), which doesn't return song metadata.
+    pub fn changes_posid(&mut self, version: u32) -> Result<Vec<(u32, Id)>> {
+        self.run_command("plchangesposid", version)?;
+
+        let mut result = Vec::new();
+        let mut pos = None;
+        for pair in self.read_pairs() {
+            let (key, value) = pair?;
+            match &*key {
+                "cpos" => pos = Some(value.parse()?),
+                "Id" => result.push((pos.take().ok_or(Error::Proto(ProtoError::NoField("cpos")))?, Id(value.parse()?))),
+                _ => (),
+            }
+        }
+        Ok(result)
     }
 
     /// Append a song into a queue
@@ -238,8 +325,20 @@ impl<S: Read + Write> Client<S> {
     }
 
     /// Insert a song into a given position in a queue
-    pub fn insert<P: ToSongPath>(&mut self, path: P, pos: usize) -> Result<usize> {
-        self.run_command("addid", (path, pos)).and_then(|_| self.read_field("Id"))
+    ///
+    /// Position is either absolute (`usize`) or a [`Position`](crate::Position), which can be relative to the current song.
+    pub fn insert<P: ToSongPath, T: ToPosition>(&mut self, path: P, pos: T) -> Result<usize> {
+        self.run_command("addid", (path, pos.to_position())).and_then(|_| self.read_field("Id"))
+    }
+
+    /// Append a song or a directory (recursively) to a queue
+    pub fn add<P: ToSongPath>(&mut self, path: P) -> Result<()> {
+        self.run_command("add", path).and_then(|_| self.expect_ok())
+    }
+
+    /// Insert a song or a directory (recursively) into a given position in a queue
+    pub fn add_at<P: ToSongPath, T: ToPosition>(&mut self, path: P, pos: T) -> Result<()> {
+        self.run_command("add", (path, pos.to_position())).and_then(|_| self.expect_ok())
     }
 
     /// Delete a song (at some position) or several songs (in a range) from a queue
@@ -249,9 +348,11 @@ impl<S: Read + Write> Client<S> {
     }
 
     /// Move a song (at a some position) or several songs (in a range) to other position in queue
-    pub fn shift<T: ToQueueRangeOrPlace>(&mut self, from: T, to: usize) -> Result<()> {
+    ///
+    /// Target position is either absolute (`usize`) or a [`Position`](crate::Position), which can be relative to the current song.
+    pub fn shift<T: ToQueueRangeOrPlace, P: ToPosition>(&mut self, from: T, to: P) -> Result<()> {
         let command = if T::is_id() { "moveid" } else { "move" };
-        self.run_command(command, (from.to_range(), to)).and_then(|_| self.expect_ok())
+        self.run_command(command, (from.to_range(), to.to_position())).and_then(|_| self.expect_ok())
     }
 
     /// Swap to songs in a queue
@@ -287,6 +388,77 @@ impl<S: Read + Write> Client<S> {
     pub fn untag<T: ToSongId>(&mut self, song: T, tag: &str) -> Result<()> {
         self.run_command("cleartagid", (song.to_song_id(), tag)).and_then(|_| self.expect_ok())
     }
+
+    /// Make queue contain exactly given songs, in given order, without interrupting playback
+    ///
+    /// Songs already in the queue are reused and moved into place, missing songs are added
+    /// and the rest are deleted, all in a single command list. Currently playing (or paused)
+    /// song is never deleted: if it's not in `uris`, it's kept at the start of the queue.
+    ///
+    /// Returns ids of queued songs, in the order of `uris`.
+    pub fn reconcile_queue(&mut self, uris: &[&str]) -> Result<Vec<Id>> {
+        let status = self.status()?;
+        let current = status.song.filter(|_| status.state != State::Stop).map(|place| place.id);
+
+        let songs = self.queue()?;
+        let mut queue: Vec<Option<Id>> = songs.iter().filter_map(|s| s.place.map(|p| Some(p.id))).collect();
+
+        // Queue entries available for reuse, by uri, with current song picked first
+        let mut available: HashMap<String, VecDeque<Id>> = HashMap::new();
+        for song in songs {
+            if let Some(place) = song.place {
+                let ids = available.entry(song.file).or_default();
+                if Some(place.id) == current {
+                    ids.push_front(place.id);
+                } else {
+                    ids.push_back(place.id);
+                }
+            }
+        }
+
+        let mut target: Vec<Option<Id>> = uris.iter().map(|uri| available.get_mut(*uri).and_then(|ids| ids.pop_front())).collect();
+        let unused: Vec<Id> = available.into_values().flatten().filter(|&id| Some(id) != current).collect();
+        if let Some(id) = current.filter(|id| !target.contains(&Some(*id))) {
+            target.insert(0, Some(id));
+        }
+        let offset = target.len() - uris.len();
+
+        // Simulate the queue to skip songs already in place
+        queue.retain(|id| !matches!(id, Some(id) if unused.contains(id)));
+
+        self.run_command("command_list_begin", ())?;
+        for &id in &unused {
+            self.run_command("deleteid", id)?;
+        }
+        for (pos, entry) in target.iter().enumerate() {
+            match *entry {
+                Some(_) if queue.get(pos) == Some(entry) => continue,
+                Some(id) => {
+                    if let Some(from) = queue.iter().position(|other| other == entry) {
+                        queue.remove(from);
+                    }
+                    queue.insert(pos, *entry);
+                    self.run_command("moveid", (id, pos))?;
+                }
+                None => {
+                    queue.insert(pos, None);
+                    self.run_command("addid", (uris[pos - offset], pos))?;
+                }
+            }
+        }
+        self.run_command("command_list_end", ())?;
+
+        let mut added = Vec::new();
+        for pair in self.read_pairs() {
+            let (key, value) = pair?;
+            if key == "Id" {
+                added.push(Id(value.parse()?));
+            }
+        }
+
+        let mut added = added.into_iter();
+        Ok(target.into_iter().skip(offset).map(|id| id.or_else(|| added.next()).unwrap_or_default()).collect())
+    }
     // }}}
 
     // Connection settings {{{
@@ -305,6 +477,13 @@ impl<S: Read + Write> Client<S> {
         self.run_command("kill", ()).and_then(|_| self.expect_ok())
     }
 
+    /// Set maximum size of binary chunks (like album art) server sends in a single response
+    ///
+    /// Bigger chunks mean less round-trips, default is 8192 bytes.
+    pub fn binarylimit(&mut self, bytes: usize) -> Result<()> {
+        self.run_command("binarylimit", bytes).and_then(|_| self.expect_ok())
+    }
+
     /// Login to MPD server with given password
     pub fn login(&mut self, password: &str) -> Result<()> {
         self.run_command("password", password).and_then(|_| self.expect_ok())
@@ -319,7 +498,38 @@ impl<S: Read + Write> Client<S> {
 
     /// List all songs in a playlist
     pub fn playlist<N: ToPlaylistName>(&mut self, name: N) -> Result<Vec<Song>> {
-        self.run_command("listplaylistinfo", name.to_name()).and_then(|_| self.read_structs("file"))
+        self.run_command("listplaylistinfo", name.to_name()).and_then(|_| self.read_songs())
+    }
+
+    /// List songs in a given range of a playlist (requires MPD 0.24)
+    pub fn playlist_range<N: ToPlaylistName, T: ToQueueRange>(&mut self, name: N, range: T) -> Result<Vec<Song>> {
+        self.run_command("listplaylistinfo", (name.to_name(), range.to_range())).and_then(|_| self.read_songs())
+    }
+
+    /// List song URIs in a playlist, without metadata
+    pub fn playlist_uris<N: ToPlaylistName>(&mut self, name: N) -> Result<Vec<String>> {
+        self.run_command("listplaylist", name.to_name()).and_then(|_| self.read_list("file"))
+    }
+
+    /// Count songs in a playlist and their total duration (requires MPD 0.24)
+    pub fn pl_length<N: ToPlaylistName>(&mut self, name: N) -> Result<(u32, Duration)> {
+        self.run_command("playlistlength", name.to_name())?;
+
+        let (mut songs, mut playtime) = (None, None);
+        for pair in self.read_pairs() {
+            let (key, value) = pair?;
+            match &*key {
+                "songs" => songs = Some(value.parse()?),
+                "playtime" => playtime = Some(Duration::try_from_secs_f64(value.parse()?)?),
+                _ => (),
+            }
+        }
+        Ok((songs.ok_or(Error::Proto(ProtoError::NoField("songs")))?, playtime.unwrap_or_default()))
+    }
+
+    /// Search songs in a playlist, case insensitive, returning only a given range of matches (requires MPD 0.24)
+    pub fn pl_search<N: ToPlaylistName, T: ToQueueRange>(&mut self, name: N, query: &Query, range: T) -> Result<Vec<Song>> {
+        self.run_command("searchplaylist", (name.to_name(), query, range.to_range())).and_then(|_| self.read_songs())
     }
 
     /// Load playlist into queue
@@ -330,6 +540,11 @@ impl<S: Read + Write> Client<S> {
         self.run_command("load", (name.to_name(), range.to_range())).and_then(|_| self.expect_ok())
     }
 
+    /// Load playlist into a given position in the queue, instead of appending it
+    pub fn load_at<T: ToQueueRange, N: ToPlaylistName, P: ToPosition>(&mut self, name: N, range: T, pos: P) -> Result<()> {
+        self.run_command("load", (name.to_name(), range.to_range(), pos.to_position())).and_then(|_| self.expect_ok())
+    }
+
     /// Save current queue into playlist
     ///
     /// If playlist with given name doesn't exist, create new one.
@@ -357,15 +572,30 @@ impl<S: Read + Write> Client<S> {
         self.run_command("playlistadd", (name.to_name(), path)).and_then(|_| self.expect_ok())
     }
 
+    /// Insert a song into a given position in a playlist (requires MPD 0.23.3)
+    pub fn pl_insert<N: ToPlaylistName, P: ToSongPath>(&mut self, name: N, path: P, pos: u32) -> Result<()> {
+        self.run_command("playlistadd", (name.to_name(), path, pos)).and_then(|_| self.expect_ok())
+    }
+
     /// Delete a song at a given position in a playlist
     pub fn pl_delete<N: ToPlaylistName>(&mut self, name: N, pos: u32) -> Result<()> {
         self.run_command("playlistdelete", (name.to_name(), pos)).and_then(|_| self.expect_ok())
     }
 
+    /// Delete songs in a given range in a playlist (requires MPD 0.23.3)
+    pub fn pl_delete_range<N: ToPlaylistName, T: ToQueueRange>(&mut self, name: N, range: T) -> Result<()> {
+        self.run_command("playlistdelete", (name.to_name(), range.to_range())).and_then(|_| self.expect_ok())
+    }
+
     /// Move song in a playlist from one position into another
     pub fn pl_shift<N: ToPlaylistName>(&mut self, name: N, from: u32, to: u32) -> Result<()> {
         self.run_command("playlistmove", (name.to_name(), from, to)).and_then(|_| self.expect_ok())
     }
+
+    /// Move songs in a given range in a playlist into another position (requires MPD 0.24)
+    pub fn pl_shift_range<N: ToPlaylistName, T: ToQueueRange>(&mut self, name: N, range: T, to: u32) -> Result<()> {
+        self.run_command("playlistmove", (name.to_name(), range.to_range(), to)).and_then(|_| self.expect_ok())
+    }
     // }}}
 
     // Database methods {{{
@@ -400,26 +630,87 @@ impl<S: Read + Write> Client<S> {
     }
 
     /// Find album art for file
-    pub fn albumart<P: ToSongPath>(&mut self, path: &P) -> Result<Vec<u8>> {
-        let mut buf = vec![];
+    ///
+    /// This looks for a cover file (like `cover.jpg`) in the song's directory,
+    /// use [`readpicture()`](Client::readpicture) to get a picture embedded into the song file.
+    ///
+    /// Returns `None` if there is no cover file.
+    pub fn albumart<P: ToSongPath>(&mut self, path: &P) -> Result<Option<AlbumArt>> {
+        let mut data = Vec::new();
+        self.albumart_to_writer(path, &mut data).map(|info| info.map(|info| AlbumArt { data, mime: info.mime }))
+    }
+
+    /// Stream album art for file into a writer, chunk by chunk
+    ///
+    /// Chunk size can be tuned with [`binarylimit()`](Client::binarylimit).
+    /// Returns `None` (and writes nothing) if there is no cover file.
+    pub fn albumart_to_writer<P: ToSongPath, W: Write>(&mut self, path: &P, writer: &mut W) -> Result<Option<AlbumArtInfo>> {
+        match self.write_art("albumart", path, writer) {
+            Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => Ok(None),
+            result => result,
+        }
+    }
+
+    /// Read picture embedded into song file
+    ///
+    /// Returns `None` if the file has no embedded picture.
+    pub fn readpicture<P: ToSongPath>(&mut self, path: &P) -> Result<Option<AlbumArt>> {
+        let mut data = Vec::new();
+        self.readpicture_to_writer(path, &mut data).map(|info| info.map(|info| AlbumArt { data, mime: info.mime }))
+    }
+
+    /// Stream picture embedded into song file into a writer, chunk by chunk
+    ///
+    /// Chunk size can be tuned with [`binarylimit()`](Client::binarylimit).
+    /// Returns `None` (and writes nothing) if the file has no embedded picture.
+    pub fn readpicture_to_writer<P: ToSongPath, W: Write>(&mut self, path: &P, writer: &mut W) -> Result<Option<AlbumArtInfo>> {
+        self.write_art("readpicture", path, writer)
+    }
+
+    /// Find cover art for song, trying embedded picture first, and falling back to a cover file
+    /// in the song's directory
+    ///
+    /// Returns `None` if there is no cover art at all.
+    pub fn cover_art<P: ToSongPath>(&mut self, path: &P) -> Result<Option<AlbumArt>> {
+        match self.readpicture(path)? {
+            Some(art) => Ok(Some(art)),
+            None => self.albumart(path),
+        }
+    }
+
+    fn write_art<P: ToSongPath, W: Write>(&mut self, command: &str, path: &P, writer: &mut W) -> Result<Option<AlbumArtInfo>> {
+        let mut info = AlbumArtInfo::default();
+        let mut offset = 0;
         loop {
-            self.run_command("albumart", (path, &*format!("{}", buf.len())))?;
-            let (_, size) = self.read_pair()?;
-            let (_, bytes) = self.read_pair()?;
-            let mut chunk = self.read_bytes(bytes.parse()?)?;
-            buf.append(&mut chunk);
+            self.run_command(command, (path, offset))?;
+
+            let mut size = None;
+            let length = loop {
+                match self.read_line()?.parse::<Reply>()? {
+                    Reply::Ok => return Ok(None),
+                    Reply::Ack(e) => return Err(Error::Server(e)),
+                    Reply::Pair(key, value) => match &*key {
+                        "size" => size = Some(value.parse()?),
+                        "type" => info.mime = Some(value),
+                        "binary" => break value.parse()?,
+                        _ => (),
+                    },
+                }
+            };
+            // Finish reading the response even if writer fails, so the connection stays usable
+            let copied = self.copy_bytes(length, writer);
             // Read empty newline
             let _ = self.read_line()?;
-            let result = self.read_line()?;
-            if result != "OK" {
-                return Err(ProtoError::NotOk)?;
-            }
+            self.expect_ok()?;
+            let copied = copied?;
 
-            if size.parse::<usize>()? == buf.len() {
+            info.size = size.ok_or(Error::Proto(ProtoError::NoField("size")))?;
+            offset += copied as usize;
+            if copied == 0 || offset >= info.size {
                 break;
             }
         }
-        Ok(buf)
+        Ok(Some(info))
     }
 
     /// Case-insensitively search for songs matching Query conditions.
@@ -429,7 +720,7 @@ impl<S: Read + Write> Client<S> {
     }
 
     fn find_generic(&mut self, cmd: &str, query: &Query, window: Window) -> Result<Vec<Song>> {
-        self.run_command(cmd, (query, window)).and_then(|_| self.read_structs("file"))
+        self.run_command(cmd, (query, window)).and_then(|_| self.read_songs())
     }
 
     /// Lists unique tags values of the specified type for songs matching the given query.
@@ -444,9 +735,14 @@ impl<S: Read + Write> Client<S> {
         self.run_command("findadd", query).and_then(|_| self.expect_ok())
     }
 
+    /// Find all songs in the db that match query and insert them into a given position in current playlist.
+    pub fn findadd_at<P: ToPosition>(&mut self, query: &Query, pos: P) -> Result<()> {
+        self.run_command("findadd", (query, "position", pos.to_position())).and_then(|_| self.expect_ok())
+    }
+
     /// Lists the contents of a directory.
     pub fn lsinfo<P: ToSongPath>(&mut self, path: P) -> Result<Vec<Song>> {
-        self.run_command("lsinfo", path).and_then(|_| self.read_structs("file"))
+        self.run_command("lsinfo", path).and_then(|_| self.read_songs())
     }
 
     /// Returns raw metadata for file
@@ -579,73 +875,180 @@ impl<S: Read + Write> Client<S> {
 
     // Sticker methods {{{
     /// Show sticker value for a given object, identified by type and uri
-    pub fn sticker(&mut self, typ: &str, uri: &str, name: &str) -> Result<String> {
-        self.run_command("sticker get", (typ, uri, name))
-            // TODO: This should parse to a `Sticker` type.
-            .and_then(|_| self.read_field::<Sticker>("sticker"))
-            .map(|s| s.value)
+    ///
+    /// The value is parsed into any type implementing `FromStr`, like `String` or a number.
+    pub fn sticker<T: ToStickerType, V: FromStr>(&mut self, typ: T, uri: &str, name: &str) -> Result<V> {
+        self.run_command("sticker get", (typ.to_sticker_type(), uri, name))
+            .and_then(|_| self.read_field::<String>("sticker"))
+            .and_then(|s| s.parse::<Sticker>()?.parse_value().map_err(From::from))
     }
 
     /// Set sticker value for a given object, identified by type and uri
-    pub fn set_sticker(&mut self, typ: &str, uri: &str, name: &str, value: &str) -> Result<()> {
-        self.run_command("sticker set", (typ, uri, name, value)).and_then(|_| self.expect_ok())
+    pub fn set_sticker<T: ToStickerType, V: fmt::Display>(&mut self, typ: T, uri: &str, name: &str, value: V) -> Result<()> {
+        self.run_command("sticker set", (typ.to_sticker_type(), uri, name, value.to_string())).and_then(|_| self.expect_ok())
     }
 
     /// Delete sticker from a given object, identified by type and uri
-    pub fn delete_sticker(&mut self, typ: &str, uri: &str, name: &str) -> Result<()> {
-        self.run_command("sticker delete", (typ, uri, name)).and_then(|_| self.expect_ok())
+    pub fn delete_sticker<T: ToStickerType>(&mut self, typ: T, uri: &str, name: &str) -> Result<()> {
+        self.run_command("sticker delete", (typ.to_sticker_type(), uri, name)).and_then(|_| self.expect_ok())
     }
 
     /// Remove all stickers from a given object, identified by type and uri
-    pub fn clear_stickers(&mut self, typ: &str, uri: &str) -> Result<()> {
-        self.run_command("sticker delete", (typ, uri)).and_then(|_| self.expect_ok())
+    pub fn clear_stickers<T: ToStickerType>(&mut self, typ: T, uri: &str) -> Result<()> {
+        self.run_command("sticker delete", (typ.to_sticker_type(), uri)).and_then(|_| self.expect_ok())
     }
 
     /// List all stickers from a given object, identified by type and uri
-    pub fn stickers(&mut self, typ: &str, uri: &str) -> Result<Vec<String>> {
-        self.run_command("sticker list", (typ, uri))
+    pub fn stickers<T: ToStickerType>(&mut self, typ: T, uri: &str) -> Result<Vec<Sticker>> {
+        self.run_command("sticker list", (typ.to_sticker_type(), uri))
             .and_then(|_| self.read_list("sticker"))
-            .map(|v| v.into_iter().map(|b| b.split_once('=').map(|x| x.1.to_owned()).unwrap()).collect())
+            .and_then(|v| v.into_iter().map(|b| b.parse().map_err(From::from)).collect())
     }
 
     /// List all stickers from a given object in a map, identified by type and uri
-    pub fn stickers_map(&mut self, typ: &str, uri: &str) -> Result<HashMap<String, String>> {
-        self.run_command("sticker list", (typ, uri)).and_then(|_| self.read_list("sticker")).map(|v| {
-            v.into_iter()
-                .map(|b| {
-                    let mut iter = b.splitn(2, '=');
-
-                    (iter.next().unwrap().to_owned(), iter.next().unwrap().to_owned())
-                })
-                .collect()
-        })
+    pub fn stickers_map<T: ToStickerType>(&mut self, typ: T, uri: &str) -> Result<HashMap<String, String>> {
+        self.stickers(typ, uri).map(|v| v.into_iter().map(|s| (s.name, s.value)).collect())
     }
 
-    /// List all (file, sticker) pairs for sticker name and objects of given type
+    /// List all (uri, sticker value) pairs for sticker name and objects of given type
     /// from given directory (identified by uri)
-    pub fn find_sticker(&mut self, typ: &str, uri: &str, name: &str) -> Result<Vec<(String, String)>> {
-        self.run_command("sticker find", (typ, uri, name)).and_then(|_| {
-            self.read_pairs()
-                .split("file")
-                .map(|rmap| {
-                    rmap.map(|map| {
-                        (
-                            map.iter().find_map(|(k, v)| if k == "file" { Some(v.to_owned()) } else { None }).unwrap(),
-                            map.iter()
-                                .find_map(|(k, v)| if k == "sticker" { Some(v.to_owned()) } else { None })
-                                .and_then(|s| s.split_once('=').map(|x| x.1.to_owned()))
-                                .unwrap(),
-                        )
-                    })
-                })
-                .collect()
-        })
-    }
-
-    /// List all files of a given type under given directory (identified by uri)
-    /// with a tag set to given value
-    pub fn find_sticker_eq(&mut self, typ: &str, uri: &str, name: &str, value: &str) -> Result<Vec<String>> {
-        self.run_command("sticker find", (typ, uri, name, "=", value)).and_then(|_| self.read_list("file"))
+    pub fn find_sticker<T: ToStickerType, V: FromStr>(&mut self, typ: T, uri: &str, name: &str) -> Result<Vec<(String, V)>> {
+        self.find_sticker_by(typ, uri, name, &StickerQuery::new())
+    }
+
+    /// List all objects of a given type under given directory (identified by uri)
+    /// with a sticker set to given value
+    pub fn find_sticker_eq<T: ToStickerType, V: fmt::Display>(&mut self, typ: T, uri: &str, name: &str, value: V) -> Result<Vec<String>> {
+        self.find_sticker_by::<_, String>(typ, uri, name, StickerQuery::new().filter(StickerOp::Equals, value))
+            .map(|v| v.into_iter().map(|(uri, _)| uri).collect())
+    }
+
+    /// List all (uri, sticker value) pairs for sticker name and objects of given type
+    /// from given directory (identified by uri), matching given query
+    ///
+    /// The query can compare sticker values, sort and limit results, see [`StickerQuery`].
+    pub fn find_sticker_by<T: ToStickerType, V: FromStr>(
+        &mut self, typ: T, uri: &str, name: &str, query: &StickerQuery,
+    ) -> Result<Vec<(String, V)>> {
+        self.run_command("sticker find", (typ.to_sticker_type(), uri, name, query)).and_then(|_| self.read_sticker_matches())
+    }
+
+    /// Increment numeric sticker value atomically, creating the sticker if it doesn't exist (MPD 0.24+)
+    pub fn inc_sticker<T: ToStickerType>(&mut self, typ: T, uri: &str, name: &str, delta: u32) -> Result<()> {
+        self.run_command("sticker inc", (typ.to_sticker_type(), uri, name, delta)).and_then(|_| self.expect_ok())
+    }
+
+    /// Decrement numeric sticker value atomically, creating the sticker if it doesn't exist (MPD 0.24+)
+    pub fn dec_sticker<T: ToStickerType>(&mut self, typ: T, uri: &str, name: &str, delta: u32) -> Result<()> {
+        self.run_command("sticker dec", (typ.to_sticker_type(), uri, name, delta)).and_then(|_| self.expect_ok())
+    }
+
+    /// List all sticker names used in sticker database (MPD 0.24+)
+    pub fn stickernames(&mut self) -> Result<Vec<String>> {
+        self.run_command("stickernames", ()).and_then(|_| self.read_list("name"))
+    }
+
+    /// List all sticker types supported by server (MPD 0.24+)
+    pub fn stickertypes(&mut self) -> Result<Vec<StickerType>> {
+        self.run_command("stickertypes", ())
+            .and_then(|_| self.read_list("stickertype"))
+            .and_then(|v| v.into_iter().map(|t| t.parse().map_err(From::from)).collect())
+    }
+
+    /// List all (sticker name, sticker type) pairs used in sticker database,
+    /// optionally only for given sticker type (MPD 0.24+)
+    pub fn stickernamestypes(&mut self, typ: Option<StickerType>) -> Result<Vec<(String, StickerType)>> {
+        match typ {
+            Some(typ) => self.run_command("stickernamestypes", typ),
+            None => self.run_command("stickernamestypes", ()),
+        }?;
+
+        let mut result = Vec::new();
+        let mut name = None;
+        for pair in self.read_pairs() {
+            let (key, value) = pair?;
+            match &*key {
+                "name" => name = Some(value),
+                "type" => result.push((name.clone().ok_or(Error::Proto(ProtoError::NoField("name")))?, value.parse()?)),
+                _ => (),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Export all stickers of objects of a given type under given directory (identified by uri)
+    ///
+    /// With MPD 0.24+ it uses `sticker find` for every known sticker name,
+    /// older servers only support song stickers, which are listed song by song.
+    pub fn export_stickers<T: ToStickerType>(&mut self, typ: T, uri: &str) -> Result<impl Iterator<Item = StickerEntry>> {
+        let typ = typ.to_sticker_type();
+        let mut entries = Vec::new();
+
+        if self.version >= Version(0, 24, 0) {
+            let mut names: Vec<String> = self.stickernamestypes(Some(typ.clone()))?.into_iter().map(|(name, _)| name).collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                for (uri, value) in self.find_sticker(&typ, uri, &name)? {
+                    entries.push(StickerEntry { uri, name: name.clone(), value });
+                }
+            }
+        } else {
+            let prefix = format!("{}/", uri);
+            for song in self.listall()? {
+                if uri.is_empty() || song.file == uri || song.file.starts_with(&prefix) {
+                    for sticker in self.stickers(&typ, &song.file)? {
+                        entries.push(StickerEntry { uri: song.file.clone(), name: sticker.name, value: sticker.value });
+                    }
+                }
+            }
+        }
+
+        entries.sort();
+        Ok(entries.into_iter())
+    }
+
+    /// Import stickers for objects of a given type
+    ///
+    /// Every sticker uri is passed through `remap` function first, so that stickers can be moved
+    /// between servers with different directory layouts; stickers it returns `None` for are skipped.
+    ///
+    /// Returns stickers, which were not imported, because their objects don't exist on the server.
+    pub fn import_stickers<T, I, F>(&mut self, typ: T, stickers: I, mut remap: F) -> Result<Vec<StickerEntry>>
+    where
+        T: ToStickerType,
+        I: IntoIterator<Item = StickerEntry>,
+        F: FnMut(&str) -> Option<String>,
+    {
+        let typ = typ.to_sticker_type();
+        let mut missing = Vec::new();
+        for entry in stickers {
+            if let Some(uri) = remap(&entry.uri) {
+                match self.set_sticker(&typ, &uri, &entry.name, &entry.value) {
+                    Ok(()) => (),
+                    Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => missing.push(StickerEntry { uri, ..entry }),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(missing)
+    }
+
+    // `sticker find` replies with an object uri line (its key depends on sticker type,
+    // like `file` for songs), followed by `sticker` line for each match
+    fn read_sticker_matches<V: FromStr>(&mut self) -> Result<Vec<(String, V)>> {
+        let mut result = Vec::new();
+        let mut uri = None;
+        for pair in self.read_pairs() {
+            let (key, value) = pair?;
+            if key == "sticker" {
+                let uri = uri.take().ok_or(Error::Proto(ProtoError::BadSticker))?;
+                result.push((uri, value.parse::<Sticker>()?.parse_value()?));
+            } else {
+                uri = Some(value);
+            }
+        }
+        Ok(result)
     }
     // }}}
 }
@@ -654,26 +1057,27 @@ impl<S: Read + Write> Client<S> {
 impl<S: Read + Write> Proto for Client<S> {
     type Stream = S;
 
-    fn read_bytes(&mut self, bytes: usize) -> Result<Vec<u8>> {
-        let mut buf = Vec::with_capacity(bytes);
-        let mut chunk = (&mut self.socket).take(bytes as u64);
-        chunk.read_to_end(&mut buf)?;
-        Ok(buf)
+    fn copy_bytes<W: Write>(&mut self, bytes: u64, writer: &mut W) -> Result<u64> {
+        let mut chunk = (&mut self.socket).take(bytes);
+        let result = io::copy(&mut chunk, writer);
+        if result.is_err() {
+            // Skip the rest of the chunk
+            io::copy(&mut chunk, &mut io::sink())?;
+        }
+        result.map_err(From::from)
     }
 
     fn read_line(&mut self) -> Result<String> {
         let mut buf = Vec::new();
         self.socket.read_until(b'\n', &mut buf)?;
-        if buf.ends_with(&[b'\n']) {
+        if buf.ends_with(b"\n") {
             buf.pop();
         }
-        let str = String::from_utf8(buf)
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))?;
-        Ok(str)
+        Ok(String::from_utf8_lossy(&buf).into_owned())
     }
 
-    fn read_pairs(&mut self) -> Pairs<Lines<&mut BufStream<S>>> {
-        Pairs((&mut self.socket).lines())
+    fn read_pairs(&mut self) -> Pairs<ByteLines<&mut BufStream<S>>> {
+        Pairs(ByteLines(&mut self.socket))
     }
 
     fn read_pair(&mut self) -> Result<(String, String)> {
@@ -688,7 +1092,9 @@ impl<S: Read + Write> Proto for Client<S> {
     where I: ToArguments {
         self.socket
             .write_all(command.as_bytes())
-            .and_then(|_| arguments.to_arguments(&mut |arg| write!(self.socket, " {}", Quoted(arg))))
+            .and_then(|_| {
+                arguments.to_raw_arguments(&mut |arg| self.socket.write_all(b" ").and_then(|_| write_quoted(&mut self.socket, arg)))
+            })
             .and_then(|_| self.socket.write(&[0x0a]))
             .and_then(|_| self.socket.flush())
             .map_err(From::from)
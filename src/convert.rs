@@ -5,7 +5,8 @@ use crate::error::{Error, ProtoError};
 use crate::output::Output;
 use crate::playlist::Playlist;
 use crate::proto::ToArguments;
-use crate::song::{self, Id, Song};
+use crate::song::{self, Id, Position, SeekTarget, Song};
+use crate::sticker::StickerType;
 use std::collections::BTreeMap;
 use std::ops::{Range, RangeFrom, RangeFull, RangeTo};
 
@@ -91,6 +92,24 @@ impl ToSeconds for Duration {
         self.as_secs_f64()
     }
 }
+
+pub trait ToSeekTarget {
+    fn to_seek_target(self) -> SeekTarget;
+}
+
+impl ToSeekTarget for SeekTarget {
+    fn to_seek_target(self) -> SeekTarget {
+        self
+    }
+}
+
+impl<T: ToSeconds> ToSeekTarget for T {
+    fn to_seek_target(self) -> SeekTarget {
+        let secs = self.to_seconds();
+        let offset = Duration::try_from_secs_f64(secs.abs()).unwrap_or_default();
+        if secs < 0.0 { SeekTarget::Backward(offset) } else { SeekTarget::Absolute(offset) }
+    }
+}
 // }}}
 
 // Queue place polymorphisms {{{
@@ -213,6 +232,24 @@ impl ToSongId for Id {
 }
 // }}}
 
+// Queue position polymorphisms {{{
+pub trait ToPosition {
+    fn to_position(self) -> Position;
+}
+
+impl ToPosition for Position {
+    fn to_position(self) -> Position {
+        self
+    }
+}
+
+impl ToPosition for usize {
+    fn to_position(self) -> Position {
+        Position::Absolute(self as u32)
+    }
+}
+// }}}
+
 // Output id polymorphisms {{{
 pub trait ToOutputId {
     fn to_output_id(self) -> u32;
@@ -230,6 +267,30 @@ impl ToOutputId for Output {
 }
 // }}}
 
+// Sticker type polymorphisms {{{
+pub trait ToStickerType {
+    fn to_sticker_type(self) -> StickerType;
+}
+
+impl ToStickerType for StickerType {
+    fn to_sticker_type(self) -> StickerType {
+        self
+    }
+}
+
+impl ToStickerType for &StickerType {
+    fn to_sticker_type(self) -> StickerType {
+        self.clone()
+    }
+}
+
+impl ToStickerType for &str {
+    fn to_sticker_type(self) -> StickerType {
+        self.parse().unwrap_or_else(|_| StickerType::Tag(self.to_owned()))
+    }
+}
+// }}}
+
 // Song play range polymorphisms {{{
 pub trait ToSongRange {
     fn to_range(self) -> song::Range;
@@ -287,18 +348,30 @@ impl ToSongRange for song::Range {
 
 pub trait ToSongPath {
     fn to_path(&self) -> &str;
+
+    fn to_path_bytes(&self) -> &[u8] {
+        self.to_path().as_bytes()
+    }
 }
 
 impl ToSongPath for Song {
     fn to_path(&self) -> &str {
         &self.file
     }
+
+    fn to_path_bytes(&self) -> &[u8] {
+        self.file_bytes()
+    }
 }
 
 impl<'a, T: ToSongPath> ToSongPath for &'a T {
     fn to_path(&self) -> &str {
         (*self).to_path()
     }
+
+    fn to_path_bytes(&self) -> &[u8] {
+        (*self).to_path_bytes()
+    }
 }
 
 impl ToSongPath for dyn AsRef<str> {
@@ -312,6 +385,11 @@ impl<T: ToSongPath> ToArguments for T {
     where F: FnMut(&str) -> Result<(), E> {
         self.to_path().to_arguments(f)
     }
+
+    fn to_raw_arguments<F, E>(&self, f: &mut F) -> Result<(), E>
+    where F: FnMut(&[u8]) -> Result<(), E> {
+        f(self.to_path_bytes())
+    }
 }
 
 impl FromIter for String {
@@ -0,0 +1,216 @@
+//! The module defines CUE sheet parser
+//!
+//! CUE sheets split whole-album files into tracks. [`CueSheet::push_to_queue()`]
+//! queues each track as a separate entry, playing only its part of the file
+//! (see [`Client::range()`]) and tagged with its title and performer.
+
+use crate::client::Client;
+use crate::error::{Error, ErrorCode, ParseError, Result, ServerError};
+use crate::song::{self, Id, Song};
+
+use std::io::{Read, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// CUE sheet track
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CueTrack {
+    /// file the track is in, as written in the sheet (usually relative to the sheet)
+    pub file: String,
+    /// track number
+    pub number: u32,
+    /// track title
+    pub title: Option<String>,
+    /// track performer (artist)
+    pub performer: Option<String>,
+    /// part of the file to play, from `INDEX 01` of the track to `INDEX 01` of the next track
+    pub range: song::Range,
+}
+
+/// Result of queueing CUE sheet tracks
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueuedTracks {
+    /// ids of added songs, in track order
+    pub ids: Vec<Id>,
+    /// ids of added songs server refused to set tags for (it allows editing tags only for remote songs)
+    pub untagged: Vec<Id>,
+}
+
+/// CUE sheet
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CueSheet {
+    /// album title
+    pub title: Option<String>,
+    /// album performer, used for tracks without their own performer
+    pub performer: Option<String>,
+    /// tracks, in order
+    pub tracks: Vec<CueTrack>,
+}
+
+impl CueSheet {
+    /// Queue all tracks, returns ids of added songs
+    ///
+    /// `base` is the directory (relative to music directory) file names in the sheet are relative to,
+    /// usually the directory of the sheet itself.
+    ///
+    /// Note MPD allows editing tags only for remote songs, so `Title` and `Artist` tags
+    /// are set on a best effort basis: songs server refused to tag are listed in
+    /// [`untagged`](QueuedTracks::untagged), other errors are returned as usual.
+    pub fn push_to_queue<S: Read + Write>(&self, client: &mut Client<S>, base: &str) -> Result<QueuedTracks> {
+        let base = base.trim_end_matches('/');
+        let mut queued = QueuedTracks { ids: Vec::with_capacity(self.tracks.len()), untagged: Vec::new() };
+        for track in &self.tracks {
+            let file = if base.is_empty() { track.file.clone() } else { format!("{}/{}", base, track.file) };
+            let id = client.push(Song { file, ..Song::default() })?;
+            client.range(id, track.range)?;
+
+            let performer = track.performer.as_ref().or(self.performer.as_ref());
+            for (tag, value) in [("Title", track.title.as_ref()), ("Artist", performer)] {
+                if let Some(value) = value {
+                    match client.tag(id, tag, value) {
+                        // "Cannot edit tags of local files"
+                        Err(Error::Server(ServerError { code: ErrorCode::Permission, .. })) => {
+                            if !queued.untagged.contains(&id) {
+                                queued.untagged.push(id);
+                            }
+                        }
+                        result => result?,
+                    }
+                }
+            }
+            queued.ids.push(id);
+        }
+        Ok(queued)
+    }
+}
+
+impl FromStr for CueSheet {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<CueSheet> {
+        let mut sheet = CueSheet::default();
+        let mut file = None;
+        // tracks of the current file, with their `INDEX 01` positions
+        let mut starts: Vec<Option<Duration>> = Vec::new();
+
+        for line in s.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim();
+            let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match &*command.to_ascii_uppercase() {
+                "FILE" => {
+                    close_file(&mut sheet.tracks, &mut starts);
+                    file = Some(unquote(rest).0);
+                }
+                "TRACK" => {
+                    let number = rest.split_whitespace().next().unwrap_or("").parse()?;
+                    let file = file.clone().ok_or_else(|| bad_value(line))?;
+                    sheet.tracks.push(CueTrack { file, number, ..CueTrack::default() });
+                    starts.push(None);
+                }
+                "TITLE" | "PERFORMER" => {
+                    let value = Some(unquote(rest).0);
+                    let in_track = !starts.is_empty();
+                    match (&*command.to_ascii_uppercase(), sheet.tracks.last_mut().filter(|_| in_track)) {
+                        ("TITLE", Some(track)) => track.title = value,
+                        ("PERFORMER", Some(track)) => track.performer = value,
+                        ("TITLE", None) => sheet.title = value,
+                        _ => sheet.performer = value,
+                    }
+                }
+                "INDEX" => {
+                    let mut parts = rest.split_whitespace();
+                    if parts.next() == Some("01") {
+                        let time = parts.next().ok_or_else(|| bad_value(line))?;
+                        *starts.last_mut().ok_or_else(|| bad_value(line))? = Some(parse_time(time)?);
+                    }
+                }
+                _ => (),
+            }
+        }
+        close_file(&mut sheet.tracks, &mut starts);
+        Ok(sheet)
+    }
+}
+
+/// Set ranges for tracks of a file, once all its tracks are known
+fn close_file(tracks: &mut [CueTrack], starts: &mut Vec<Option<Duration>>) {
+    let first = tracks.len() - starts.len();
+    for (idx, track) in tracks[first..].iter_mut().enumerate() {
+        let start = starts[idx].unwrap_or_default();
+        let end = starts.get(idx + 1).copied().flatten();
+        track.range = song::Range(start, end);
+    }
+    starts.clear();
+}
+
+/// Split quoted (or bare) value from the rest of the line
+fn unquote(s: &str) -> (String, &str) {
+    match s.strip_prefix('"').and_then(|s| s.split_once('"')) {
+        Some((value, rest)) => (value.to_owned(), rest.trim()),
+        None => {
+            let (value, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+            (value.to_owned(), rest.trim())
+        }
+    }
+}
+
+/// Parse `MM:SS:FF` time, with 75 frames per second
+fn parse_time(s: &str) -> Result<Duration> {
+    let mut parts = s.split(':').map(str::parse::<u64>);
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(min), Some(sec), Some(frames), None) => {
+            let (min, sec, frames) = (min?, sec?, frames?);
+            Ok(Duration::from_secs(min * 60 + sec) + Duration::from_nanos(frames * 1_000_000_000 / 75))
+        }
+        _ => Err(bad_value(s)),
+    }
+}
+
+fn bad_value(s: &str) -> Error {
+    Error::Parse(ParseError::BadValue(s.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CUE: &str = r#"REM GENRE Rock
+PERFORMER "Album Artist"
+TITLE "Album"
+FILE "album.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "First"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Second"
+    PERFORMER "Guest"
+    INDEX 00 03:10:00
+    INDEX 01 03:12:37
+FILE "bonus.flac" WAVE
+  TRACK 03 AUDIO
+    TITLE "Bonus"
+    INDEX 01 00:00:00
+"#;
+
+    #[test]
+    fn parse_sheet() {
+        let sheet: CueSheet = CUE.parse().unwrap();
+        assert_eq!(sheet.title.as_deref(), Some("Album"));
+        assert_eq!(sheet.performer.as_deref(), Some("Album Artist"));
+        assert_eq!(sheet.tracks.len(), 3);
+
+        let second = Duration::from_secs(192) + Duration::from_nanos(493_333_333);
+        assert_eq!(sheet.tracks[0].range, song::Range(Duration::ZERO, Some(second)));
+        assert_eq!(sheet.tracks[1].range, song::Range(second, None));
+        assert_eq!(sheet.tracks[1].performer.as_deref(), Some("Guest"));
+        assert_eq!(sheet.tracks[1].range.to_string(), "192.493:");
+        assert_eq!(sheet.tracks[2].file, "bonus.flac");
+        assert_eq!(sheet.tracks[2].number, 3);
+        assert_eq!(sheet.tracks[2].range, song::Range(Duration::ZERO, None));
+    }
+
+    #[test]
+    fn bad_time() {
+        assert!("FILE a.flac WAVE\nTRACK 01 AUDIO\nINDEX 01 00:xx:00".parse::<CueSheet>().is_err());
+    }
+}
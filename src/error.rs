@@ -276,6 +276,8 @@ pub enum ParseError {
     BadChans(ParseIntError),
     /// unknown state in state status field
     BadState(String),
+    /// unknown replay gain mode
+    BadReplayGain(String),
     /// unknown error code in `ACK` response
     BadErrorCode(usize),
 }
@@ -305,6 +307,7 @@ impl fmt::Display for ParseError {
             E::BadBits(_) => "invalid audio format bits",
             E::BadChans(_) => "invalid audio format channels",
             E::BadState(_) => "invalid playing state",
+            E::BadReplayGain(_) => "invalid replay gain mode",
             E::BadErrorCode(_) => "unknown error code",
         };
 
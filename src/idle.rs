@@ -141,6 +141,12 @@ impl<'a, S: 'a + Read + Write> IdleGuard<'a, S> {
         forget(self);
         result
     }
+
+    /// Leave idle mode without waiting, returns events which occurred since entering it
+    pub fn cancel(self) -> Result<Vec<Subsystem>, Error> {
+        self.0.run_command("noidle", ())?;
+        self.get()
+    }
 }
 
 impl<'a, S: 'a + Read + Write> Drop for IdleGuard<'a, S> {
@@ -41,26 +41,45 @@ pub mod status;
 pub mod song;
 pub mod output;
 pub mod playlist;
+pub mod playlist_file;
 pub mod plugin;
 pub mod stats;
 pub mod search;
 pub mod message;
 pub mod idle;
 pub mod mount;
-mod sticker;
+pub mod albumart;
+pub mod sticker;
+pub mod library;
+pub mod bookmarks;
+pub mod queue;
+pub mod cue;
+pub mod volume;
+#[cfg(feature = "xspf")]
+pub mod xspf;
 
 mod proto;
 pub mod client;
 
+pub use albumart::{AlbumArt, AlbumArtInfo, ArtCache};
+pub use bookmarks::Bookmarks;
 pub use client::Client;
+pub use cue::{CueSheet, CueTrack, QueuedTracks};
 pub use idle::{Idle, Subsystem};
+pub use library::Library;
 pub use message::{Channel, Message};
 pub use mount::{Mount, Neighbor};
-pub use output::Output;
+pub use output::{AllowedFormat, Output};
 pub use playlist::Playlist;
+pub use playlist_file::{PlaylistEntry, PlaylistFile, PlaylistFormat};
 pub use plugin::Plugin;
+pub use queue::QueueMirror;
 pub use search::{Query, Term};
-pub use song::{Id, Song};
+pub use song::{Id, Position, SeekTarget, Song};
 pub use stats::Stats;
-pub use status::{ReplayGain, State, Status};
+pub use status::{ConsumeMode, ReplayGain, SingleMode, State, Status};
+pub use sticker::{Sticker, StickerEntry, StickerOp, StickerQuery, StickerSort, StickerType};
 pub use version::Version;
+pub use volume::VolumeRamp;
+#[cfg(feature = "xspf")]
+pub use xspf::{Xspf, XspfResolution, XspfTrack};
@@ -0,0 +1,161 @@
+//! The module defines a ratings and play statistics layer built on top of stickers
+//!
+//! MPD has no notion of song ratings or play counts, so clients store them as song
+//! [stickers](crate::sticker). [`Library`] uses the same sticker names and value formats
+//! as other popular clients (like myMPD and Cantata), so the data is shared between them:
+//!
+//!   - `rating`: integer from 0 to 10 (so 5 stars with half-stars),
+//!   - `playCount`: integer number of plays,
+//!   - `lastPlayed`: time of the last play, in seconds since Unix epoch.
+//!
+//! Stickers are addressed by UTF-8 song paths, so songs with names which are not valid UTF-8
+//! (see [`Song::is_lossy()`]) can't be rated or counted, and methods return an error for them.
+
+use crate::client::Client;
+use crate::convert::ToSongPath;
+use crate::error::{Error, ErrorCode, ParseError, Result, ServerError};
+use crate::search::Query;
+use crate::song::Song;
+use crate::sticker::{StickerOp, StickerQuery, StickerSort, StickerType};
+use crate::version::Version;
+
+use std::io::{Read, Write};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Sticker name for song rating
+pub const RATING: &str = "rating";
+/// Sticker name for song play count
+pub const PLAY_COUNT: &str = "playCount";
+/// Sticker name for song last play time
+pub const LAST_PLAYED: &str = "lastPlayed";
+
+/// Maximum song rating
+pub const MAX_RATING: u8 = 10;
+
+/// Ratings and play statistics for songs, stored in song stickers
+///
+/// Borrows a client for the time of use, like [`IdleGuard`](crate::idle::IdleGuard) does.
+pub struct Library<'a, S: 'a + Read + Write>(&'a mut Client<S>);
+
+impl<'a, S: 'a + Read + Write> Library<'a, S> {
+    /// Create library helper for a given client
+    pub fn new(client: &'a mut Client<S>) -> Library<'a, S> {
+        Library(client)
+    }
+
+    /// Get song rating (0 to 10), if it was rated
+    pub fn rating<P: ToSongPath>(&mut self, song: P) -> Result<Option<u8>> {
+        self.get(sticker_uri(&song)?, RATING)
+    }
+
+    /// Set song rating (0 to 10)
+    pub fn set_rating<P: ToSongPath>(&mut self, song: P, rating: u8) -> Result<()> {
+        if rating > MAX_RATING {
+            return Err(Error::Parse(ParseError::BadValue(rating.to_string())));
+        }
+        self.0.set_sticker(StickerType::Song, sticker_uri(&song)?, RATING, rating)
+    }
+
+    /// Remove song rating
+    pub fn clear_rating<P: ToSongPath>(&mut self, song: P) -> Result<()> {
+        match self.0.delete_sticker(StickerType::Song, sticker_uri(&song)?, RATING) {
+            Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Get number of times the song was played
+    pub fn play_count<P: ToSongPath>(&mut self, song: P) -> Result<u32> {
+        self.get(sticker_uri(&song)?, PLAY_COUNT).map(Option::unwrap_or_default)
+    }
+
+    /// Increment song play count
+    ///
+    /// With MPD 0.24+ the counter is incremented atomically on server side,
+    /// older servers fall back to reading and writing the value.
+    pub fn increment_play_count<P: ToSongPath>(&mut self, song: P) -> Result<()> {
+        let path = sticker_uri(&song)?;
+        if self.0.version >= Version(0, 24, 0) {
+            self.0.inc_sticker(StickerType::Song, path, PLAY_COUNT, 1)
+        } else {
+            let count: u32 = self.get(path, PLAY_COUNT)?.unwrap_or_default();
+            self.0.set_sticker(StickerType::Song, path, PLAY_COUNT, count + 1)
+        }
+    }
+
+    /// Get time the song was last played, if it was played at all
+    pub fn last_played<P: ToSongPath>(&mut self, song: P) -> Result<Option<SystemTime>> {
+        self.get(sticker_uri(&song)?, LAST_PLAYED).map(|v| v.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)))
+    }
+
+    /// Set time the song was last played
+    pub fn set_last_played<P: ToSongPath>(&mut self, song: P, time: SystemTime) -> Result<()> {
+        let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        self.0.set_sticker(StickerType::Song, sticker_uri(&song)?, LAST_PLAYED, secs)
+    }
+
+    /// Record a song play: increment its play count and set last played time to now
+    pub fn mark_played<P: ToSongPath>(&mut self, song: P) -> Result<()> {
+        self.increment_play_count(&song)?;
+        self.set_last_played(&song, SystemTime::now())
+    }
+
+    /// Get up to `n` best rated songs matching a query, best first
+    ///
+    /// Empty query matches all songs in database.
+    pub fn top_rated(&mut self, query: &Query, n: usize) -> Result<Vec<(Song, u8)>> {
+        // Values are parsed here, so a single malformed sticker doesn't fail the whole call
+        let rated: Vec<(String, String)> = if self.0.version >= Version(0, 24, 0) {
+            let mut sticker_query = StickerQuery::new();
+            sticker_query.filter(StickerOp::GreaterInt, 0).sort(StickerSort::ValueInt, true);
+            if query.is_empty() {
+                sticker_query.window((0, n as u32));
+            }
+            self.0.find_sticker_by(StickerType::Song, "", RATING, &sticker_query)?
+        } else {
+            self.0.find_sticker(StickerType::Song, "", RATING)?
+        };
+        // Zero rating means "not rated" for other clients
+        let mut rated: Vec<(String, u8)> =
+            rated.into_iter().filter_map(|(uri, rating)| rating.parse().ok().filter(|&rating| rating > 0).map(|rating| (uri, rating))).collect();
+        rated.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut result = Vec::new();
+        if query.is_empty() {
+            for (uri, rating) in rated.into_iter().take(n) {
+                if let Some(song) = self.0.lsinfo(Song { file: uri, ..Song::default() })?.into_iter().next() {
+                    result.push((song, rating));
+                }
+            }
+        } else {
+            let mut songs = self.0.find(query, None)?;
+            for (uri, rating) in rated {
+                if result.len() >= n {
+                    break;
+                }
+                if let Some(pos) = songs.iter().position(|s| s.file == uri) {
+                    result.push((songs.swap_remove(pos), rating));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn get<V: FromStr>(&mut self, path: &str, name: &str) -> Result<Option<V>> {
+        match self.0.sticker(StickerType::Song, path, name) {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Song path to use as a sticker uri, fails for songs with lossy (non-UTF-8) paths
+fn sticker_uri<P: ToSongPath>(song: &P) -> Result<&str> {
+    let path = song.to_path();
+    if path.as_bytes() != song.to_path_bytes() {
+        return Err(Error::Parse(ParseError::BadValue(path.to_owned())));
+    }
+    Ok(path)
+}
@@ -1,7 +1,8 @@
 //! The module describes output
 
 use crate::convert::FromIter;
-use crate::error::{Error, ProtoError};
+use crate::error::{Error, ParseError, ProtoError};
+use crate::status::AudioFormatMask;
 
 /// Sound output
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -19,6 +20,33 @@ pub struct Output {
     pub attributes: Vec<(String, String)>
 }
 
+/// Format an output is allowed to use
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AllowedFormat {
+    /// format mask
+    pub mask: AudioFormatMask,
+    /// DSD is sent as DoP (DSD over PCM), set with `=dop` suffix
+    pub dop: bool,
+}
+
+impl Output {
+    /// Formats the output is allowed to use (from `allowed_formats` attribute, MPD 0.22+)
+    ///
+    /// Returns `None` if the attribute is not reported (e.g. not supported by the output plugin).
+    pub fn allowed_formats(&self) -> Option<Result<Vec<AllowedFormat>, ParseError>> {
+        self.attributes.iter().find(|(name, _)| name == "allowed_formats").map(|(_, value)| {
+            value
+                .split_whitespace()
+                .map(|format| match format.strip_suffix("=dop") {
+                    Some(format) => format.parse().map(|mask| AllowedFormat { mask, dop: true }),
+                    None => format.parse().map(|mask| AllowedFormat { mask, dop: false }),
+                })
+                .collect()
+        })
+    }
+}
+
 impl FromIter for Output {
     // Implement FromIter directly so that we can parse plugin-specific attributes
     fn from_iter<I: Iterator<Item = Result<(String, String), Error>>>(iter: I) -> Result<Output, Error> {
@@ -63,3 +91,21 @@ impl FromIter for Output {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::status::SampleFormat;
+
+    #[test]
+    fn allowed_formats() {
+        let mut output = Output { id: 0, plugin: "alsa".into(), name: "DAC".into(), enabled: true, attributes: Vec::new() };
+        assert_eq!(output.allowed_formats(), None);
+
+        output.attributes.push(("allowed_formats".into(), "96000:16:* dsd64:*=dop".into()));
+        let formats = output.allowed_formats().unwrap().unwrap();
+        assert_eq!(formats[0], AllowedFormat { mask: "96000:16:*".parse().unwrap(), dop: false });
+        assert_eq!(formats[1].mask.format, Some(SampleFormat::Dsd(64)));
+        assert!(formats[1].dop);
+    }
+}
@@ -0,0 +1,279 @@
+//! The module defines playlist file formats, used to move playlists between MPD and other players
+//!
+//! MPD loads playlist files only from its own playlist directory, which remote clients
+//! usually can't write to. [`PlaylistFile`] reads and writes extended M3U (including M3U8)
+//! and PLS files on the client side, and pushes their entries to the queue or a stored playlist.
+//!
+//! Playlist files often contain absolute paths, while MPD wants URIs relative to its music
+//! directory, so use [`PlaylistFile::relative_to()`] after parsing a file, and
+//! [`PlaylistFile::absolute_in()`] before writing one for another player.
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::song::{Id, Song};
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Playlist file format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// extended M3U, with `#EXTINF` lines (always read and written as UTF-8, like M3U8)
+    M3u,
+    /// PLS (version 2)
+    Pls,
+}
+
+impl PlaylistFormat {
+    /// Guess format from file extension (`.m3u`, `.m3u8` or `.pls`)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<PlaylistFormat> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match &*ext {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u),
+            "pls" => Some(PlaylistFormat::Pls),
+            _ => None,
+        }
+    }
+}
+
+/// Playlist file entry
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlaylistEntry {
+    /// song path or URL, as written in the file
+    pub uri: String,
+    /// song title
+    pub title: Option<String>,
+    /// song duration
+    pub duration: Option<Duration>,
+}
+
+impl<'a> From<&'a Song> for PlaylistEntry {
+    fn from(song: &'a Song) -> PlaylistEntry {
+        PlaylistEntry { uri: song.file.clone(), title: song.title.clone(), duration: song.duration }
+    }
+}
+
+/// Playlist file contents
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlaylistFile {
+    /// playlist entries, in order
+    pub entries: Vec<PlaylistEntry>,
+}
+
+impl PlaylistFile {
+    /// Parse playlist file
+    ///
+    /// Parsing is lenient: unknown lines and invalid durations are skipped.
+    pub fn parse<R: BufRead>(format: PlaylistFormat, reader: R) -> Result<PlaylistFile> {
+        let mut lines = Vec::new();
+        for line in reader.split(b'\n') {
+            let line = String::from_utf8_lossy(&line?).into_owned();
+            lines.push(line.trim_start_matches('\u{feff}').trim_end_matches('\r').to_owned());
+        }
+
+        let entries = match format {
+            PlaylistFormat::M3u => parse_m3u(&lines),
+            PlaylistFormat::Pls => parse_pls(&lines),
+        };
+        Ok(PlaylistFile { entries })
+    }
+
+    /// Write playlist file
+    pub fn write<W: Write>(&self, format: PlaylistFormat, mut writer: W) -> Result<()> {
+        match format {
+            PlaylistFormat::M3u => {
+                writeln!(writer, "#EXTM3U")?;
+                for entry in &self.entries {
+                    if entry.duration.is_some() || entry.title.is_some() {
+                        let duration = entry.duration.map_or(-1, |d| d.as_secs() as i64);
+                        writeln!(writer, "#EXTINF:{},{}", duration, entry.title.as_deref().unwrap_or(""))?;
+                    }
+                    writeln!(writer, "{}", entry.uri)?;
+                }
+            }
+            PlaylistFormat::Pls => {
+                writeln!(writer, "[playlist]")?;
+                for (n, entry) in self.entries.iter().enumerate().map(|(i, e)| (i + 1, e)) {
+                    writeln!(writer, "File{}={}", n, entry.uri)?;
+                    if let Some(ref title) = entry.title {
+                        writeln!(writer, "Title{}={}", n, title)?;
+                    }
+                    writeln!(writer, "Length{}={}", n, entry.duration.map_or(-1, |d| d.as_secs() as i64))?;
+                }
+                writeln!(writer, "NumberOfEntries={}", self.entries.len())?;
+                writeln!(writer, "Version=2")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite local paths into URIs relative to MPD music directory
+    ///
+    /// Absolute paths (and `file://` URLs) inside music directory are made relative to it,
+    /// other entries (like stream URLs) are left as is.
+    pub fn relative_to(&mut self, music_directory: &str) -> &mut PlaylistFile {
+        let root = music_directory.trim_end_matches('/');
+        for entry in &mut self.entries {
+            let path = match entry.uri.strip_prefix("file://") {
+                Some(path) => percent_decode(path),
+                None => entry.uri.clone(),
+            };
+            if let Some(relative) = path.strip_prefix(root).and_then(|p| p.strip_prefix('/')) {
+                entry.uri = relative.to_owned();
+            }
+        }
+        self
+    }
+
+    /// Rewrite URIs relative to MPD music directory into absolute paths
+    ///
+    /// Use it to export playlists for players which don't know about MPD music directory.
+    /// URLs and absolute paths are left as is.
+    pub fn absolute_in(&mut self, music_directory: &str) -> &mut PlaylistFile {
+        let root = music_directory.trim_end_matches('/');
+        for entry in &mut self.entries {
+            if !entry.uri.contains("://") && !entry.uri.starts_with('/') {
+                entry.uri = format!("{}/{}", root, entry.uri);
+            }
+        }
+        self
+    }
+
+    /// Build playlist file from current queue
+    pub fn from_queue<S: Read + Write>(client: &mut Client<S>) -> Result<PlaylistFile> {
+        Ok(PlaylistFile::from_songs(&client.queue()?))
+    }
+
+    /// Build playlist file from a stored playlist
+    pub fn from_playlist<S: Read + Write>(client: &mut Client<S>, name: &str) -> Result<PlaylistFile> {
+        Ok(PlaylistFile::from_songs(&client.playlist(name)?))
+    }
+
+    fn from_songs(songs: &[Song]) -> PlaylistFile {
+        PlaylistFile { entries: songs.iter().map(PlaylistEntry::from).collect() }
+    }
+
+    /// Append all entries to the queue, returns ids of added songs
+    pub fn push_to_queue<S: Read + Write>(&self, client: &mut Client<S>) -> Result<Vec<Id>> {
+        self.entries.iter().map(|entry| client.push(entry_song(entry))).collect()
+    }
+
+    /// Append all entries to a stored playlist, creating it if needed
+    pub fn push_to_playlist<S: Read + Write>(&self, client: &mut Client<S>, name: &str) -> Result<()> {
+        self.entries.iter().try_for_each(|entry| client.pl_push(name, entry_song(entry)))
+    }
+}
+
+fn entry_song(entry: &PlaylistEntry) -> Song {
+    Song { file: entry.uri.clone(), ..Song::default() }
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    value.trim().parse::<f64>().ok().and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+fn parse_m3u(lines: &[String]) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut info = PlaylistEntry::default();
+    for line in lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<duration> [attributes],<title>
+            let (duration, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+            info.duration = duration.split_whitespace().next().and_then(parse_duration);
+            info.title = Some(title.trim()).filter(|t| !t.is_empty()).map(str::to_owned);
+        } else if !line.starts_with('#') {
+            entries.push(PlaylistEntry { uri: line.to_owned(), ..std::mem::take(&mut info) });
+        }
+    }
+    entries
+}
+
+fn parse_pls(lines: &[String]) -> Vec<PlaylistEntry> {
+    let mut entries: BTreeMap<u32, PlaylistEntry> = BTreeMap::new();
+    for (key, value) in lines.iter().filter_map(|line| line.split_once('=')) {
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        let (field, n) = match key.find(|c: char| c.is_ascii_digit()) {
+            Some(idx) => key.split_at(idx),
+            None => continue,
+        };
+        let n = match n.parse() {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        let entry = entries.entry(n).or_default();
+        match field {
+            "file" => entry.uri = value.to_owned(),
+            "title" => entry.title = Some(value.to_owned()).filter(|t| !t.is_empty()),
+            "length" => entry.duration = parse_duration(value),
+            _ => (),
+        }
+    }
+    entries.into_values().filter(|entry| !entry.uri.is_empty()).collect()
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], s.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                result.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                result.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const M3U: &str = "\u{feff}#EXTM3U\r\n#EXTINF:123,Artist - Title\r\n/music/a/b.flac\r\n\r\n# comment\r\nhttp://radio/stream\r\n";
+
+    const PLS: &str = "[playlist]\nFile2=http://radio/stream\nFile1=/music/a/b.flac\nTitle1=Artist - Title\nLength1=123\nLength2=-1\nNumberOfEntries=2\n";
+
+    fn expected() -> Vec<PlaylistEntry> {
+        vec![
+            PlaylistEntry { uri: "/music/a/b.flac".into(), title: Some("Artist - Title".into()), duration: Some(Duration::from_secs(123)) },
+            PlaylistEntry { uri: "http://radio/stream".into(), title: None, duration: None },
+        ]
+    }
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(PlaylistFile::parse(PlaylistFormat::M3u, M3U.as_bytes()).unwrap().entries, expected());
+        assert_eq!(PlaylistFile::parse(PlaylistFormat::Pls, PLS.as_bytes()).unwrap().entries, expected());
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let file = PlaylistFile { entries: expected() };
+        for format in [PlaylistFormat::M3u, PlaylistFormat::Pls] {
+            let mut output = Vec::new();
+            file.write(format, &mut output).unwrap();
+            assert_eq!(PlaylistFile::parse(format, &output[..]).unwrap(), file);
+        }
+    }
+
+    #[test]
+    fn rewrite_paths() {
+        let mut file = PlaylistFile { entries: expected() };
+        file.entries.push(PlaylistEntry { uri: "file:///music/c%20d.mp3".into(), ..Default::default() });
+        file.relative_to("/music/");
+        let uris: Vec<_> = file.entries.iter().map(|e| &*e.uri).collect();
+        assert_eq!(uris, vec!["a/b.flac", "http://radio/stream", "c d.mp3"]);
+
+        file.absolute_in("/music");
+        assert_eq!(file.entries[2].uri, "/music/c d.mp3");
+        assert_eq!(PlaylistFormat::from_path("x/Y.M3U8"), Some(PlaylistFormat::M3u));
+    }
+}
@@ -6,25 +6,116 @@ use bufstream::BufStream;
 use crate::convert::FromIter;
 use crate::error::{Error, ParseError, ProtoError, Result};
 use crate::reply::Reply;
+use crate::song::Song;
 
 use std::fmt;
-use std::io::{self, Lines, Read, Write};
+use std::io::{self, BufRead, Read, Write};
 use std::result::Result as StdResult;
 use std::str::FromStr;
 
+/// Lines of a server response as raw bytes
+///
+/// Unlike `BufRead::lines()` it doesn't fail on invalid UTF-8,
+/// so a single badly encoded filename can't break a whole response.
+pub struct ByteLines<B>(pub B);
+
+impl<B: BufRead> Iterator for ByteLines<B> {
+    type Item = io::Result<Vec<u8>>;
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        let mut buf = Vec::new();
+        match self.0.read_until(b'\n', &mut buf) {
+            Ok(0) => None,
+            Ok(_) => {
+                if buf.ends_with(b"\n") {
+                    buf.pop();
+                    if buf.ends_with(b"\r") {
+                        buf.pop();
+                    }
+                }
+                Some(Ok(buf))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
 pub struct Pairs<I>(pub I);
 
+impl<I> Pairs<I>
+where I: Iterator<Item = io::Result<Vec<u8>>>
+{
+    fn next_reply(&mut self) -> Option<Result<(Reply, Vec<u8>)>> {
+        self.0.next().map(|v| {
+            v.map_err(Error::Io)
+                .and_then(|line| String::from_utf8_lossy(&line).parse::<Reply>().map(|r| (r, line)).map_err(Error::Parse))
+        })
+    }
+
+    /// Same as `next()`, but keeps pair value as raw bytes, exactly as server sent it
+    pub fn next_raw(&mut self) -> Option<Result<(String, Vec<u8>)>> {
+        match self.next_reply() {
+            Some(Ok((Reply::Pair(a, b), line))) => {
+                if std::str::from_utf8(&line).is_ok() {
+                    Some(Ok((a, b.into_bytes())))
+                } else {
+                    let value = line.splitn(2, |&c| c == b':').nth(1).unwrap_or_default();
+                    Some(Ok((a, trim_bytes(value).to_vec())))
+                }
+            }
+            None | Some(Ok((Reply::Ok, _))) => None,
+            Some(Ok((Reply::Ack(e), _))) => Some(Err(Error::Server(e))),
+            Some(Err(e)) => Some(Err(e)),
+        }
+    }
+
+    /// Collect songs, keeping raw bytes of filenames which are not valid UTF-8
+    pub fn songs(mut self) -> Result<Vec<Song>> {
+        let mut songs = Vec::new();
+        let mut map = Vec::new();
+        let mut raw_file = None;
+
+        while let Some(pair) = self.next_raw() {
+            let (key, raw) = pair?;
+            let (value, raw) = match String::from_utf8(raw) {
+                Ok(value) => (value, None),
+                Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), Some(e.into_bytes())),
+            };
+
+            if key == "file" {
+                if !map.is_empty() {
+                    songs.push(Song { raw_file: raw_file.take(), ..FromIter::from_iter(map.drain(..).map(Ok))? });
+                }
+                raw_file = raw;
+                map.push((key, value));
+            } else if !map.is_empty() {
+                map.push((key, value));
+            }
+        }
+
+        if !map.is_empty() {
+            songs.push(Song { raw_file, ..FromIter::from_iter(map.into_iter().map(Ok))? });
+        }
+
+        Ok(songs)
+    }
+}
+
+/// Strip ASCII whitespace around byte string
+fn trim_bytes(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|c| !c.is_ascii_whitespace()).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|c| !c.is_ascii_whitespace()).map_or(start, |end| end + 1);
+    &bytes[start..end]
+}
+
 impl<I> Iterator for Pairs<I>
-where I: Iterator<Item = io::Result<String>>
+where I: Iterator<Item = io::Result<Vec<u8>>>
 {
     type Item = Result<(String, String)>;
     fn next(&mut self) -> Option<Result<(String, String)>> {
-        let reply: Option<Result<Reply>> =
-            self.0.next().map(|v| v.map_err(Error::Io).and_then(|s| s.parse::<Reply>().map_err(Error::Parse)));
-        match reply {
-            Some(Ok(Reply::Pair(a, b))) => Some(Ok((a, b))),
-            None | Some(Ok(Reply::Ok)) => None,
-            Some(Ok(Reply::Ack(e))) => Some(Err(Error::Server(e))),
+        match self.next_reply() {
+            Some(Ok((Reply::Pair(a, b), _))) => Some(Ok((a, b))),
+            None | Some(Ok((Reply::Ok, _))) => None,
+            Some(Ok((Reply::Ack(e), _))) => Some(Err(Error::Server(e))),
             Some(Err(e)) => Some(Err(e)),
         }
     }
@@ -39,7 +130,7 @@ pub struct Maps<'a, I: 'a> {
 }
 
 impl<'a, I> Iterator for Maps<'a, I>
-where I: Iterator<Item = io::Result<String>>
+where I: Iterator<Item = io::Result<Vec<u8>>>
 {
     type Item = Result<Vec<(String, String)>>;
     fn next(&mut self) -> Option<Result<Vec<(String, String)>>> {
@@ -84,7 +175,7 @@ where I: Iterator<Item = io::Result<String>>
 }
 
 impl<I> Pairs<I>
-where I: Iterator<Item = io::Result<String>>
+where I: Iterator<Item = io::Result<Vec<u8>>>
 {
     pub fn split<'a, 'b: 'a>(&'a mut self, f: &'b str) -> Maps<'a, I> {
         Maps { pairs: self, sep: f, value: None, done: false, first: true }
@@ -96,9 +187,9 @@ where I: Iterator<Item = io::Result<String>>
 pub trait Proto {
     type Stream: Read + Write;
 
-    fn read_bytes(&mut self, bytes: usize) -> Result<Vec<u8>>;
+    fn copy_bytes<W: Write>(&mut self, bytes: u64, writer: &mut W) -> Result<u64>;
     fn read_line(&mut self) -> Result<String>;
-    fn read_pairs(&mut self) -> Pairs<Lines<&mut BufStream<Self::Stream>>>;
+    fn read_pairs(&mut self) -> Pairs<ByteLines<&mut BufStream<Self::Stream>>>;
 
     fn run_command<I>(&mut self, command: &str, arguments: I) -> Result<()>
     where I: ToArguments;
@@ -108,6 +199,11 @@ pub trait Proto {
         self.read_pairs().split(key).map(|v| v.and_then(|v| FromIter::from_iter(v.into_iter().map(Ok)))).collect()
     }
 
+    /// Read songs, keeping raw bytes of filenames which are not valid UTF-8
+    fn read_songs(&mut self) -> Result<Vec<Song>> {
+        self.read_pairs().songs()
+    }
+
     fn read_list(&mut self, key: &'static str) -> Result<Vec<String>> {
         self.read_pairs().filter(|r| r.as_ref().map(|(a, _)| *a == key).unwrap_or(true)).map(|r| r.map(|(_, b)| b)).collect()
     }
@@ -168,6 +264,12 @@ pub trait Proto {
 pub trait ToArguments {
     fn to_arguments<F, E>(&self, _: &mut F) -> StdResult<(), E>
     where F: FnMut(&str) -> StdResult<(), E>;
+
+    /// Same as `to_arguments()`, but allows arguments which are not valid UTF-8 (like raw song paths)
+    fn to_raw_arguments<F, E>(&self, f: &mut F) -> StdResult<(), E>
+    where F: FnMut(&[u8]) -> StdResult<(), E> {
+        self.to_arguments(&mut |arg: &str| f(arg.as_bytes()))
+    }
 }
 
 impl ToArguments for () {
@@ -201,10 +303,15 @@ argument_for_display! {f32}
 argument_for_display! {f64}
 argument_for_display! {usize}
 argument_for_display! {crate::status::ReplayGain}
+argument_for_display! {crate::status::SingleMode}
+argument_for_display! {crate::status::ConsumeMode}
 argument_for_display! {String}
 argument_for_display! {crate::song::Id}
 argument_for_display! {crate::song::Range}
+argument_for_display! {crate::song::Position}
+argument_for_display! {crate::song::SeekTarget}
 argument_for_display! {crate::message::Channel}
+argument_for_display! {crate::sticker::StickerType}
 
 macro_rules! argument_for_tuple {
     ( $($t:ident: $T: ident),+ ) => {
@@ -218,6 +325,16 @@ macro_rules! argument_for_tuple {
                      )*
                     Ok(())
                 }
+
+            fn to_raw_arguments<F, E>(&self, f: &mut F) -> StdResult<(), E>
+                where F: FnMut(&[u8]) -> StdResult<(), E>
+                {
+                    let ($(ref $t,)*) = *self;
+                    $(
+                        $t.to_raw_arguments(f)?;
+                     )*
+                    Ok(())
+                }
         }
     };
 }
@@ -235,6 +352,14 @@ impl<'a, T: ToArguments> ToArguments for &'a [T] {
         }
         Ok(())
     }
+
+    fn to_raw_arguments<F, E>(&self, f: &mut F) -> StdResult<(), E>
+    where F: FnMut(&[u8]) -> StdResult<(), E> {
+        for arg in *self {
+            arg.to_raw_arguments(f)?
+        }
+        Ok(())
+    }
 }
 
 pub struct Quoted<'a, D: fmt::Display + 'a + ?Sized>(pub &'a D);
@@ -250,4 +375,70 @@ impl<'a, D: fmt::Display + 'a + ?Sized> fmt::Display for Quoted<'a, D> {
     }
 }
 
+/// Write byte string argument quoted and escaped the same way as [`Quoted`] does
+pub fn write_quoted<W: Write>(writer: &mut W, arg: &[u8]) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    for chunk in arg.split_inclusive(|&c| c == b'\\' || c == b'"') {
+        match chunk.split_last() {
+            Some((&c, rest)) if c == b'\\' || c == b'"' => {
+                writer.write_all(rest)?;
+                writer.write_all(&[b'\\', c])?;
+            }
+            _ => writer.write_all(chunk)?,
+        }
+    }
+    writer.write_all(b"\"")
+}
+
 // }}}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn invalid_utf8_pairs() {
+        let data = &b"file: caf\xe9.flac\nTitle: Caf\xc3\xa9\nOK\n"[..];
+
+        let pairs: Vec<_> = Pairs(ByteLines(data)).collect::<Result<_>>().unwrap();
+        assert_eq!(pairs, vec![("file".to_owned(), "caf\u{fffd}.flac".to_owned()), ("Title".to_owned(), "Caf\u{e9}".to_owned())]);
+
+        let mut pairs = Pairs(ByteLines(data));
+        assert_eq!(pairs.next_raw().unwrap().unwrap(), ("file".to_owned(), b"caf\xe9.flac".to_vec()));
+        assert_eq!(pairs.next_raw().unwrap().unwrap(), ("Title".to_owned(), "Caf\u{e9}".as_bytes().to_vec()));
+        assert!(pairs.next_raw().is_none());
+    }
+
+    #[test]
+    fn songs_with_invalid_utf8_file() {
+        let data = &b"file: a\xff.flac\nTitle: A\nfile: b.flac\nTitle: B\nfile: c\xfe\xfd.flac\nOK\n"[..];
+
+        let songs = Pairs(ByteLines(data)).songs().unwrap();
+        assert_eq!(songs.len(), 3);
+        assert_eq!(songs[0].file, "a\u{fffd}.flac");
+        assert_eq!(songs[0].raw_file.as_deref(), Some(&b"a\xff.flac"[..]));
+        assert_eq!(songs[0].title.as_deref(), Some("A"));
+        assert_eq!(songs[1].file, "b.flac");
+        assert_eq!(songs[1].raw_file, None);
+        assert_eq!(songs[1].title.as_deref(), Some("B"));
+        assert_eq!(songs[2].raw_file.as_deref(), Some(&b"c\xfe\xfd.flac"[..]));
+    }
+
+    #[test]
+    fn trim_raw_value() {
+        assert_eq!(trim_bytes(b"  a b\t\r"), b"a b");
+        assert_eq!(trim_bytes(b"   "), b"");
+        assert_eq!(trim_bytes(b""), b"");
+    }
+
+    #[test]
+    fn quote_raw_argument() {
+        let mut output = Vec::new();
+        write_quoted(&mut output, b"a \"b\"\\c\xe9").unwrap();
+        assert_eq!(output, b"\"a \\\"b\\\"\\\\c\xe9\"");
+
+        let mut output = Vec::new();
+        write_quoted(&mut output, b"\xff\\").unwrap();
+        assert_eq!(output, b"\"\xff\\\\\"");
+    }
+}
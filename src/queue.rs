@@ -0,0 +1,119 @@
+//! The module defines a local mirror of MPD queue
+//!
+//! Clients showing the queue usually keep their own copy of it, and refresh
+//! it on every [`Queue`](crate::Subsystem::Queue) event. Downloading the whole
+//! queue each time is wasteful, as MPD can tell which songs changed since a given
+//! queue version (see [`changes_posid()`](crate::Client::changes_posid)), and
+//! [`queue_songs()`](crate::Client::queue_songs) can fetch just those songs. [`QueueMirror`] applies
+//! these changes incrementally.
+
+use crate::client::Client;
+use crate::error::{Error, ErrorCode, Result, ServerError};
+use crate::idle::Subsystem;
+use crate::song::{Id, QueuePlace, Song};
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Local copy of MPD queue, kept in sync incrementally
+#[derive(Debug, Clone, Default)]
+pub struct QueueMirror {
+    songs: Vec<Song>,
+    version: Option<u32>,
+}
+
+impl QueueMirror {
+    /// Create empty mirror, it is filled with the whole queue on first sync
+    pub fn new() -> QueueMirror {
+        QueueMirror::default()
+    }
+
+    /// Songs in the queue, as of last sync
+    pub fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    /// Queue version of last sync, `None` if it was never synced
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// Handle idle events
+    ///
+    /// Call it with subsystems returned from [`wait()`](crate::Idle::wait) or
+    /// [`IdleGuard::get()`](crate::idle::IdleGuard::get), it does nothing
+    /// unless there is a [`Queue`](Subsystem::Queue) event.
+    pub fn update<S: Read + Write>(&mut self, client: &mut Client<S>, subsystems: &[Subsystem]) -> Result<bool> {
+        if subsystems.contains(&Subsystem::Queue) {
+            self.sync(client)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Bring mirror in sync with server's queue
+    ///
+    /// Only positions and ids of changed songs are requested (with `plchangesposid`),
+    /// metadata is requested just for songs which are new to the mirror.
+    ///
+    /// Returns `true` if the queue was changed.
+    pub fn sync<S: Read + Write>(&mut self, client: &mut Client<S>) -> Result<bool> {
+        let status = client.status()?;
+        let version = match self.version {
+            Some(version) if version == status.queue_version => return Ok(false),
+            Some(version) => version,
+            None => return self.reload(client, status.queue_version),
+        };
+
+        let len = status.queue_len as usize;
+        let changes = client.changes_posid(version)?;
+
+        // Songs which just moved are taken from our copy, while songs new to us,
+        // or reported changed without being moved (e.g. their tags changed), are fetched
+        let mut known: HashMap<Id, Song> = self.songs.iter().filter_map(|s| s.place.map(|p| (p.id, s.clone()))).collect();
+        let fetch: Vec<Id> = changes
+            .iter()
+            .filter(|&&(pos, id)| !matches!(known.get(&id).and_then(|s| s.place), Some(p) if p.pos != pos))
+            .map(|&(_, id)| id)
+            .collect();
+        let fetched = match client.queue_songs(&[], &fetch) {
+            // Queue changed again since we asked for status
+            Err(Error::Server(ServerError { code: ErrorCode::NoExist, .. })) => return self.reload(client, status.queue_version),
+            result => result?,
+        };
+        for song in fetched {
+            if let Some(place) = song.place {
+                known.insert(place.id, song);
+            }
+        }
+
+        let mut changed = Vec::with_capacity(changes.len());
+        for (pos, id) in changes {
+            match known.get(&id) {
+                Some(song) => changed.push(Song { place: song.place.map(|p| QueuePlace { pos, ..p }), ..song.clone() }),
+                None => return self.reload(client, status.queue_version),
+            }
+        }
+
+        let mut songs = std::mem::take(&mut self.songs);
+        songs.truncate(len);
+        for song in changed {
+            match song.place.map(|p| p.pos as usize) {
+                Some(pos) if pos < songs.len() => songs[pos] = song,
+                Some(pos) if pos == songs.len() => songs.push(song),
+                // Changes don't fit our copy, start from scratch
+                _ => return self.reload(client, status.queue_version),
+            }
+        }
+
+        self.songs = songs;
+        self.version = Some(status.queue_version);
+        Ok(true)
+    }
+
+    fn reload<S: Read + Write>(&mut self, client: &mut Client<S>, version: u32) -> Result<bool> {
+        self.songs = client.queue()?;
+        self.version = Some(version);
+        Ok(true)
+    }
+}
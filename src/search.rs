@@ -18,6 +18,13 @@ pub enum Term<'a> {
     Base,
     #[cfg_attr(feature = "serde", serde(rename = "modified-since"))]
     LastMod,
+    /// Song priority in the queue, only useful with queue searches
+    #[cfg_attr(feature = "serde", serde(rename = "prio"))]
+    Priority,
+    /// Song audio format, use with [`Operation::Matches`] and
+    /// [`AudioFormatMask`](crate::status::AudioFormatMask) to match masks with `*` fields
+    #[cfg_attr(feature = "serde", serde(rename = "AudioFormat"))]
+    AudioFormat,
     Tag(Cow<'a, str>),
 }
 
@@ -27,7 +34,13 @@ pub enum Operation {
     NotEquals,
     Contains,
     #[cfg_attr(feature = "serde", serde(rename = "starts_with"))]
-    StartsWith
+    StartsWith,
+    /// The only operation supported for [`Term::Priority`]
+    #[cfg_attr(feature = "serde", serde(rename = ">="))]
+    AtLeast,
+    /// Audio format mask match (or regular expression match for tags, MPD 0.24+)
+    #[cfg_attr(feature = "serde", serde(rename = "=~"))]
+    Matches,
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -60,6 +73,12 @@ impl<'a> Filter<'a> {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Window(Option<(u32, u32)>);
 
+impl Window {
+    pub fn range(&self) -> Option<(u32, u32)> {
+        self.0
+    }
+}
+
 impl From<(u32, u32)> for Window {
     fn from(window: (u32, u32)) -> Window {
         Window(Some(window))
@@ -82,6 +101,10 @@ impl<'a> Query<'a> {
         Query { filters: Vec::new() }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
     pub fn and<'b: 'a, V: 'b + Into<Cow<'b, str>>>(&mut self, term: Term<'b>, value: V) -> &mut Query<'a> {
         self.filters.push(Filter::new(term, value));
         self
@@ -91,6 +114,12 @@ impl<'a> Query<'a> {
         self.filters.push(Filter::new_with_op(term, value, op));
         self
     }
+
+    /// Match queued songs with priority not less than given one
+    pub fn priority_at_least(&mut self, prio: u8) -> &mut Query<'a> {
+        self.filters.push(Filter::new_with_op(Term::Priority, prio.to_string(), Operation::AtLeast));
+        self
+    }
 }
 
 impl<'a> fmt::Display for Term<'a> {
@@ -100,6 +129,8 @@ impl<'a> fmt::Display for Term<'a> {
             Term::File => "file",
             Term::Base => "base",
             Term::LastMod => "modified-since",
+            Term::Priority => "prio",
+            Term::AudioFormat => "AudioFormat",
             Term::Tag(ref tag) => tag,
         })
     }
@@ -118,7 +149,9 @@ impl fmt::Display for Operation {
             Operation::Equals => "==",
             Operation::NotEquals => "!=",
             Operation::Contains => "contains",
-            Operation::StartsWith => "starts_with"
+            Operation::StartsWith => "starts_with",
+            Operation::AtLeast => ">=",
+            Operation::Matches => "=~",
         })
     }
 }
@@ -142,6 +175,8 @@ impl<'a> ToArguments for &'a Filter<'a> {
                     &Quoted(&self.what).to_string()
                 ))
             }
+            // Priority is a bare number
+            Term::Priority => f(&format!("({} {} {})", &self.typ, &self.how, &self.what)),
             _ => {
                 f(&format!(
                     "({} {} {})",
@@ -172,7 +207,12 @@ impl<'a> ToArguments for &'a Query<'a> {
                 })?;
             }
             // println!("Singly escaped query string: {}", &qs);
-            f(&qs)
+            // MPD expects AND chains as a single parenthesized expression
+            if self.filters.len() > 1 {
+                f(&format!("({})", qs))
+            } else {
+                f(&qs)
+            }
         } else {
             Ok(())
         }
@@ -218,7 +258,25 @@ mod test {
         let mut query = Query::new();
         let finished = query.and(Term::Tag("albumartist".into()), "Mac DeMarco").and(Term::Tag("album".into()), "Salad Days");
         let output = collect(&*finished);
-        assert_eq!(output, vec!["albumartist", "Mac DeMarco", "album", "Salad Days"]);
+        assert_eq!(output, vec!["((albumartist == \"Mac DeMarco\") AND (album == \"Salad Days\"))"]);
+    }
+
+    #[test]
+    fn priority_format() {
+        let mut query = Query::new();
+        query.and(Term::Tag("artist".into()), "Mac DeMarco").priority_at_least(10);
+        let output = collect(&query);
+        assert_eq!(output, vec!["((artist == \"Mac DeMarco\") AND (prio >= 10))"]);
+
+        assert_eq!(collect(&*Query::new().priority_at_least(10)), vec!["(prio >= 10)"]);
+    }
+
+    #[test]
+    fn audio_format_format() {
+        let mask: crate::status::AudioFormatMask = "44100:*:2".parse().unwrap();
+        let mut query = Query::new();
+        query.and_with_op(Term::AudioFormat, Operation::Matches, mask.to_string());
+        assert_eq!(collect(&query), vec!["(AudioFormat =~ \"44100:*:2\")"]);
     }
 
     #[test]
@@ -8,7 +8,7 @@ use std::str::FromStr;
 use std::time::Duration;
 
 /// Song ID
-#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Default)]
 pub struct Id(pub u32);
 
 impl fmt::Display for Id {
@@ -45,6 +45,52 @@ pub struct QueuePlace {
     pub prio: u8,
 }
 
+/// Position in the queue to add or move songs to
+///
+/// Relative positions need MPD 0.23 or later, and only make sense while there is a current song.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Position {
+    /// absolute zero-based position
+    Absolute(u32),
+    /// position after the current song, `AfterCurrent(0)` is right after it (i.e. "play next")
+    AfterCurrent(u32),
+    /// position before the current song, `BeforeCurrent(0)` is right before it
+    BeforeCurrent(u32),
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Position::Absolute(pos) => write!(f, "{}", pos),
+            Position::AfterCurrent(offset) => write!(f, "+{}", offset),
+            Position::BeforeCurrent(offset) => write!(f, "-{}", offset),
+        }
+    }
+}
+
+/// Target of a seek in the current song
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SeekTarget {
+    /// absolute position from the start of the song
+    Absolute(Duration),
+    /// offset forward from the current position
+    Forward(Duration),
+    /// offset backward from the current position
+    Backward(Duration),
+}
+
+impl fmt::Display for SeekTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SeekTarget::Absolute(pos) => write!(f, "{}", pos.as_secs_f64()),
+            SeekTarget::Forward(offset) => write!(f, "+{}", offset.as_secs_f64()),
+            SeekTarget::Backward(offset) => write!(f, "-{}", offset.as_secs_f64()),
+        }
+    }
+}
+
 /// Song range
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -58,34 +104,64 @@ impl Default for Range {
 
 impl fmt::Display for Range {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.as_secs().fmt(f)?;
+        fmt_secs(self.0, f)?;
         f.write_str(":")?;
         if let Some(v) = self.1 {
-            v.as_secs().fmt(f)?;
+            fmt_secs(v, f)?;
         }
         Ok(())
     }
 }
 
+/// Format duration as seconds, with up to millisecond precision, the reverse of `parse_secs()`
+fn fmt_secs(duration: Duration, f: &mut fmt::Formatter) -> fmt::Result {
+    match duration.subsec_millis() {
+        0 => write!(f, "{}", duration.as_secs()),
+        millis => write!(f, "{}.{}", duration.as_secs(), format!("{:03}", millis).trim_end_matches('0')),
+    }
+}
+
+impl FromStr for Position {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Position, ParseError> {
+        if let Some(offset) = s.strip_prefix('+') {
+            Ok(Position::AfterCurrent(offset.parse()?))
+        } else if let Some(offset) = s.strip_prefix('-') {
+            Ok(Position::BeforeCurrent(offset.parse()?))
+        } else {
+            Ok(Position::Absolute(s.parse()?))
+        }
+    }
+}
+
 impl FromStr for Range {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Range, ParseError> {
-        let mut splits = s.split('-').flat_map(|v| v.parse().into_iter());
-        match (splits.next(), splits.next()) {
-            (Some(s), Some(e)) => Ok(Range(Duration::from_secs(s), Some(Duration::from_secs(e)))),
-            (None, Some(e)) => Ok(Range(Duration::from_secs(0), Some(Duration::from_secs(e)))),
-            (Some(s), None) => Ok(Range(Duration::from_secs(s), None)),
-            (None, None) => Ok(Range(Duration::from_secs(0), None)),
-        }
+        let (start, end) = s.split_once('-').unwrap_or((s, ""));
+        let start = if start.is_empty() { Duration::from_secs(0) } else { parse_secs(start)? };
+        let end = if end.is_empty() { None } else { Some(parse_secs(end)?) };
+        Ok(Range(start, end))
+    }
+}
+
+/// Parse seconds with fractional part (like `12.345`), keeping up to millisecond precision
+fn parse_secs(s: &str) -> Result<Duration, ParseError> {
+    let (secs, frac) = s.split_once('.').unwrap_or((s, ""));
+    if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseError::BadValue(s.to_owned()));
     }
+    let millis = format!("{:0<3}", &frac[..frac.len().min(3)]).parse::<u64>()?;
+    Ok(Duration::from_secs(secs.parse()?) + Duration::from_millis(millis))
 }
 
 /// Song data
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Song {
-    /// filename
+    /// filename (lossily decoded, if it is not valid UTF-8, see [`file_bytes()`](Song::file_bytes))
     pub file: String,
+    /// raw filename bytes, set only if the filename is not valid UTF-8
+    pub raw_file: Option<Vec<u8>>,
     /// name (for streams)
     pub name: Option<String>,
     /// title
@@ -104,6 +180,21 @@ pub struct Song {
     pub tags: Vec<(String, String)>,
 }
 
+impl Song {
+    /// Filename exactly as sent by the server
+    ///
+    /// Use it instead of `file` to address songs with non-UTF-8 names,
+    /// e.g. ones coming from Latin-1 encoded file systems.
+    pub fn file_bytes(&self) -> &[u8] {
+        self.raw_file.as_deref().unwrap_or(self.file.as_bytes())
+    }
+
+    /// Check if `file` is only a lossy (display) version of the real filename
+    pub fn is_lossy(&self) -> bool {
+        self.raw_file.is_some()
+    }
+}
+
 impl FromIter for Song {
     /// build song from map
     fn from_iter<I: Iterator<Item = Result<(String, String), Error>>>(iter: I) -> Result<Song, Error> {
@@ -142,3 +233,18 @@ impl FromIter for Song {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn range_round_trip() {
+        let range: Range = "12.345-67.890".parse().unwrap();
+        assert_eq!(range, Range(Duration::from_millis(12_345), Some(Duration::from_millis(67_890))));
+        assert_eq!(range.to_string(), "12.345:67.89");
+        assert_eq!("0.5-".parse::<Range>().unwrap(), Range(Duration::from_millis(500), None));
+        assert_eq!("3-4".parse::<Range>().unwrap().to_string(), "3:4");
+        assert!("1.x-2".parse::<Range>().is_err());
+    }
+}
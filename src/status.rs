@@ -4,7 +4,9 @@ use crate::convert::FromIter;
 use crate::error::{Error, ParseError};
 use crate::song::{Id, QueuePlace};
 
+use std::collections::BTreeMap;
 use std::fmt;
+use std::num::ParseIntError;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -12,16 +14,17 @@ use std::time::Duration;
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct Status {
-    /// volume (0-100, or -1 if volume is unavailable (e.g. for HTTPD output type)
-    pub volume: i8,
+    /// volume (0-100), `None` if there is no mixer (MPD 0.23+ doesn't report volume then,
+    /// older servers report -1, e.g. for HTTPD output type)
+    pub volume: Option<i8>,
     /// repeat mode
     pub repeat: bool,
     /// random mode
     pub random: bool,
     /// single mode
-    pub single: bool,
+    pub single: SingleMode,
     /// consume mode
-    pub consume: bool,
+    pub consume: ConsumeMode,
     /// queue version number
     pub queue_version: u32,
     /// queue length
@@ -55,6 +58,12 @@ pub struct Status {
     pub error: Option<String>,
     /// replay gain mode
     pub replaygain: Option<ReplayGain>,
+    /// name of the partition the client is connected to (MPD 0.22+)
+    pub partition: Option<String>,
+    /// name of the last stored playlist loaded into the queue (MPD 0.24+)
+    pub lastloadedplaylist: Option<String>,
+    /// fields (or `single`/`consume` modes) this version of the library doesn't know about, as reported by the server
+    pub extra: BTreeMap<String, String>,
 }
 
 impl FromIter for Status {
@@ -64,12 +73,23 @@ impl FromIter for Status {
         for res in iter {
             let line = res?;
             match &*line.0 {
-                "volume" => result.volume = line.1.parse()?,
+                "volume" => result.volume = Some(line.1.parse()?).filter(|&volume: &i8| volume >= 0),
 
                 "repeat" => result.repeat = &*line.1 == "1",
                 "random" => result.random = &*line.1 == "1",
-                "single" => result.single = &*line.1 == "1",
-                "consume" => result.consume = &*line.1 == "1",
+                // Modes from newer servers are left `Off`, with raw value kept in `extra`
+                "single" => match line.1.parse() {
+                    Ok(mode) => result.single = mode,
+                    Err(_) => {
+                        result.extra.insert(line.0, line.1);
+                    }
+                },
+                "consume" => match line.1.parse() {
+                    Ok(mode) => result.consume = mode,
+                    Err(_) => {
+                        result.extra.insert(line.0, line.1);
+                    }
+                },
 
                 "playlist" => result.queue_version = line.1.parse()?,
                 "playlistlength" => result.queue_len = line.1.parse()?,
@@ -103,12 +123,17 @@ impl FromIter for Status {
                 "bitrate" => result.bitrate = Some(line.1.parse()?),
                 "xfade" => result.crossfade = Some(Duration::from_secs(line.1.parse()?)),
                 "mixrampdb" => result.mixrampdb = line.1.parse::<f32>()?,
-                "mixrampdelay" => result.mixrampdelay = Some(Duration::from_secs_f64(line.1.parse()?)),
+                // `nan` (or negative value) means mixramp is disabled
+                "mixrampdelay" => result.mixrampdelay = Duration::try_from_secs_f64(line.1.parse()?).ok(),
                 "audio" => result.audio = Some(line.1.parse()?),
                 "updating_db" => result.updating_db = Some(line.1.parse()?),
                 "error" => result.error = Some(line.1.to_owned()),
                 "replay_gain_mode" => result.replaygain = Some(line.1.parse()?),
-                _ => (),
+                "partition" => result.partition = Some(line.1),
+                "lastloadedplaylist" => result.lastloadedplaylist = Some(line.1).filter(|name| !name.is_empty()),
+                _ => {
+                    result.extra.insert(line.0, line.1);
+                }
             }
         }
 
@@ -116,54 +141,152 @@ impl FromIter for Status {
     }
 }
 
+/// Audio sample format
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// integer samples of a given resolution in bits (8, 16, 24 or 32)
+    Bits(u8),
+    /// 32-bit floating point samples
+    Float,
+    /// DSD with a given multiplier of 44.1 kHz (e.g. 64 for DSD64),
+    /// 0 means any multiplier (only makes sense in [`AudioFormatMask`])
+    Dsd(u32),
+}
+
+impl fmt::Display for SampleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SampleFormat::Bits(bits) => write!(f, "{}", bits),
+            SampleFormat::Float => f.write_str("f"),
+            SampleFormat::Dsd(_) => f.write_str("dsd"),
+        }
+    }
+}
+
 /// Audio playback format
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct AudioFormat {
-    /// Sample rate, kbps.
-    /// For DSD, to align with MPD's internal handling, the returned rate will be in kilobytes per second instead.
+    /// Sample rate, Hz.
+    /// For DSD, to align with MPD's internal handling, the rate is in bytes per second instead
+    /// (i.e. 44100 * the DSD multiplier / 8).
     /// See https://mpd.readthedocs.io/en/latest/user.html#audio-output-format.
     pub rate: u32,
-    /// Sample resolution in bits, can be 0 for floating point resolution or 1 for DSD.
-    pub bits: u8,
+    /// Sample format
+    pub format: SampleFormat,
     /// Number of channels.
     pub chans: u8,
 }
 
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.format {
+            SampleFormat::Dsd(mul) => write!(f, "dsd{}:{}", mul, self.chans),
+            format => write!(f, "{}:{}:{}", self.rate, format, self.chans),
+        }
+    }
+}
+
 impl FromStr for AudioFormat {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<AudioFormat, ParseError> {
-        if s.contains("dsd") {
-            // DSD format string only contains two terms: "dsd..." and number of channels.
-            // To shoehorn into our current AudioFormat struct, use the following conversion:
-            // - Sample rate: 44100 * the DSD multiplier / 8. For example, DSD64 is sampled at 2.8224MHz.
-            // - Bits: 1 (DSD is a sequence of single-bit values, or PDM).
-            // - Channels: as usual.
-            let mut it = s.split(':');
-            let dsd_mul: u32 = it.next().ok_or(ParseError::NoRate).and_then(|v| v[3..].parse().map_err(ParseError::BadRate))?;
-            return Ok(AudioFormat {
-                rate: dsd_mul * 44100 / 8,
-                bits: 1,
-                chans: it.next().ok_or(ParseError::NoChans).and_then(|v| v.parse().map_err(ParseError::BadChans))?,
-            });
-        }
-        let mut it = s.split(':');
+        let mask: AudioFormatMask = s.parse()?;
         Ok(AudioFormat {
-            rate: it.next().ok_or(ParseError::NoRate).and_then(|v| v.parse().map_err(ParseError::BadRate))?,
-            bits: it.next().ok_or(ParseError::NoBits).and_then(
-                |v| {
-                    if v == "f" {
-                        Ok(0)
-                    } else {
-                        v.parse().map_err(ParseError::BadBits)
-                    }
-                },
-            )?,
-            chans: it.next().ok_or(ParseError::NoChans).and_then(|v| v.parse().map_err(ParseError::BadChans))?,
+            rate: mask.rate.ok_or(ParseError::NoRate)?,
+            format: mask.format.filter(|f| *f != SampleFormat::Dsd(0)).ok_or(ParseError::NoBits)?,
+            chans: mask.chans.ok_or(ParseError::NoChans)?,
         })
     }
 }
 
+/// Audio format mask, with `*` (`None`) fields matching any value
+///
+/// Used in output `allowed_formats` attribute (see [`Output::allowed_formats()`](crate::Output::allowed_formats))
+/// and in `AudioFormat =~` filters (see [`Term::AudioFormat`](crate::search::Term::AudioFormat)).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct AudioFormatMask {
+    /// sample rate, Hz (bytes per second for DSD, like in [`AudioFormat`])
+    pub rate: Option<u32>,
+    /// sample format
+    pub format: Option<SampleFormat>,
+    /// number of channels
+    pub chans: Option<u8>,
+}
+
+impl From<AudioFormat> for AudioFormatMask {
+    fn from(format: AudioFormat) -> AudioFormatMask {
+        AudioFormatMask { rate: Some(format.rate), format: Some(format.format), chans: Some(format.chans) }
+    }
+}
+
+fn fmt_mask_field<T: fmt::Display>(value: Option<T>, f: &mut fmt::Formatter) -> fmt::Result {
+    match value {
+        Some(value) => write!(f, "{}", value),
+        None => f.write_str("*"),
+    }
+}
+
+impl fmt::Display for AudioFormatMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.format {
+            Some(SampleFormat::Dsd(mul)) if mul > 0 => write!(f, "dsd{}:", mul)?,
+            format => {
+                fmt_mask_field(self.rate, f)?;
+                f.write_str(":")?;
+                fmt_mask_field(format, f)?;
+                f.write_str(":")?;
+            }
+        }
+        fmt_mask_field(self.chans, f)
+    }
+}
+
+impl FromStr for AudioFormatMask {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<AudioFormatMask, ParseError> {
+        let mut it = s.split(':');
+        let first = it.next().ok_or(ParseError::NoRate)?;
+
+        // DSD format string only contains two terms: "dsd..." and number of channels.
+        if let Some(mul) = first.strip_prefix("dsd") {
+            let mul: u32 = mul.parse().map_err(ParseError::BadRate)?;
+            let chans = it.next().ok_or(ParseError::NoChans)?;
+            return Ok(AudioFormatMask {
+                rate: Some(mul.checked_mul(44100).ok_or_else(rate_overflow)? / 8),
+                format: Some(SampleFormat::Dsd(mul)),
+                chans: parse_mask_field(chans, ParseError::BadChans)?,
+            });
+        }
+
+        let rate: Option<u32> = parse_mask_field(first, ParseError::BadRate)?;
+        let format = match it.next().ok_or(ParseError::NoBits)? {
+            "*" => None,
+            "f" => Some(SampleFormat::Float),
+            "dsd" => Some(SampleFormat::Dsd(match rate {
+                Some(rate) => rate.checked_mul(8).ok_or_else(rate_overflow)? / 44100,
+                None => 0,
+            })),
+            bits => Some(SampleFormat::Bits(bits.parse().map_err(ParseError::BadBits)?)),
+        };
+        let chans = parse_mask_field(it.next().ok_or(ParseError::NoChans)?, ParseError::BadChans)?;
+        Ok(AudioFormatMask { rate, format, chans })
+    }
+}
+
+// `ParseIntError` can't be constructed directly, so get one from parsing a number too big for `u32`
+fn rate_overflow() -> ParseError {
+    ParseError::BadRate(u64::MAX.to_string().parse::<u32>().unwrap_err())
+}
+
+fn parse_mask_field<T: FromStr<Err = ParseIntError>>(s: &str, err: fn(ParseIntError) -> ParseError) -> Result<Option<T>, ParseError> {
+    match s {
+        "*" => Ok(None),
+        s => s.parse().map(Some).map_err(err),
+    }
+}
+
 /// Playback state
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
@@ -189,6 +312,88 @@ impl FromStr for State {
     }
 }
 
+/// Single mode: stop playback after current song, or repeat it if repeat mode is on
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SingleMode {
+    /// single mode is off
+    #[default]
+    Off,
+    /// single mode is on
+    On,
+    /// single mode is on for the current song only, and is turned off after it (MPD 0.21+)
+    Oneshot,
+}
+
+impl From<bool> for SingleMode {
+    fn from(value: bool) -> SingleMode {
+        if value { SingleMode::On } else { SingleMode::Off }
+    }
+}
+
+impl FromStr for SingleMode {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<SingleMode, ParseError> {
+        match s {
+            "0" => Ok(SingleMode::Off),
+            "1" => Ok(SingleMode::On),
+            "oneshot" => Ok(SingleMode::Oneshot),
+            _ => Err(ParseError::BadValue(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for SingleMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            SingleMode::Off => "0",
+            SingleMode::On => "1",
+            SingleMode::Oneshot => "oneshot",
+        })
+    }
+}
+
+/// Consume mode: remove songs from the queue once played
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConsumeMode {
+    /// consume mode is off
+    #[default]
+    Off,
+    /// consume mode is on
+    On,
+    /// consume mode is on for the current song only, and is turned off after it (MPD 0.24+)
+    Oneshot,
+}
+
+impl From<bool> for ConsumeMode {
+    fn from(value: bool) -> ConsumeMode {
+        if value { ConsumeMode::On } else { ConsumeMode::Off }
+    }
+}
+
+impl FromStr for ConsumeMode {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<ConsumeMode, ParseError> {
+        match s {
+            "0" => Ok(ConsumeMode::Off),
+            "1" => Ok(ConsumeMode::On),
+            "oneshot" => Ok(ConsumeMode::Oneshot),
+            _ => Err(ParseError::BadValue(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for ConsumeMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ConsumeMode::Off => "0",
+            ConsumeMode::On => "1",
+            ConsumeMode::Oneshot => "oneshot",
+        })
+    }
+}
+
 /// Replay gain mode
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -212,7 +417,7 @@ impl FromStr for ReplayGain {
             "track" => Ok(Track),
             "album" => Ok(Album),
             "auto" => Ok(Auto),
-            _ => Err(ParseError::BadValue(s.to_owned())),
+            _ => Err(ParseError::BadReplayGain(s.to_owned())),
         }
     }
 }
@@ -228,3 +433,79 @@ impl fmt::Display for ReplayGain {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn modes_round_trip() {
+        for mode in [SingleMode::Off, SingleMode::On, SingleMode::Oneshot] {
+            assert_eq!(mode.to_string().parse::<SingleMode>(), Ok(mode));
+        }
+        for mode in [ConsumeMode::Off, ConsumeMode::On, ConsumeMode::Oneshot] {
+            assert_eq!(mode.to_string().parse::<ConsumeMode>(), Ok(mode));
+        }
+        assert!("2".parse::<SingleMode>().is_err());
+    }
+
+    #[test]
+    fn replaygain_round_trip() {
+        for gain in [ReplayGain::Off, ReplayGain::Track, ReplayGain::Album, ReplayGain::Auto] {
+            assert_eq!(gain.to_string().parse::<ReplayGain>(), Ok(gain));
+        }
+        assert_eq!("loud".parse::<ReplayGain>(), Err(ParseError::BadReplayGain("loud".into())));
+    }
+
+    #[test]
+    fn audio_format_round_trip() {
+        for s in ["44100:16:2", "192000:f:2", "dsd64:2", "48000:24:6"] {
+            assert_eq!(s.parse::<AudioFormat>().unwrap().to_string(), s);
+        }
+        let dsd: AudioFormat = "dsd128:2".parse().unwrap();
+        assert_eq!(dsd, AudioFormat { rate: 705_600, format: SampleFormat::Dsd(128), chans: 2 });
+        assert_eq!("352800:dsd:2".parse::<AudioFormat>().unwrap().format, SampleFormat::Dsd(64));
+        assert_eq!("*:16:2".parse::<AudioFormat>(), Err(ParseError::NoRate));
+    }
+
+    #[test]
+    fn audio_format_mask() {
+        for s in ["*:*:*", "44100:*:2", "*:f:*", "*:dsd:*", "dsd64:*"] {
+            assert_eq!(s.parse::<AudioFormatMask>().unwrap().to_string(), s);
+        }
+        assert!(matches!("dsd4294967295:2".parse::<AudioFormatMask>(), Err(ParseError::BadRate(_))));
+        assert!(matches!("4294967295:dsd:2".parse::<AudioFormatMask>(), Err(ParseError::BadRate(_))));
+        let mask: AudioFormatMask = "96000:*:*".parse().unwrap();
+        assert_eq!(mask, AudioFormatMask { rate: Some(96000), format: None, chans: None });
+    }
+
+    #[test]
+    fn status_fields() {
+        let pairs = [("volume", "-1"), ("partition", "default"), ("lastloadedplaylist", "Lounge"), ("mixrampdelay", "nan"), ("newfield", "42")];
+        let status = Status::from_iter(pairs.iter().map(|&(k, v)| Ok((k.to_owned(), v.to_owned())))).unwrap();
+        assert_eq!(status.volume, None);
+        assert_eq!(status.partition.as_deref(), Some("default"));
+        assert_eq!(status.lastloadedplaylist.as_deref(), Some("Lounge"));
+        assert_eq!(status.mixrampdelay, None);
+        assert_eq!(status.extra.get("newfield").map(|v| &**v), Some("42"));
+
+        let pairs = [("single", "oneshot"), ("consume", "sometimes")];
+        let status = Status::from_iter(pairs.iter().map(|&(k, v)| Ok((k.to_owned(), v.to_owned())))).unwrap();
+        assert_eq!(status.single, SingleMode::Oneshot);
+        assert_eq!(status.consume, ConsumeMode::Off);
+        assert_eq!(status.extra.get("consume").map(|v| &**v), Some("sometimes"));
+
+        let status = Status::from_iter(std::iter::once(Ok(("volume".to_owned(), "75".to_owned())))).unwrap();
+        assert_eq!(status.volume, Some(75));
+
+        let status = Status::from_iter(std::iter::empty()).unwrap();
+        assert_eq!(status.volume, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn modes_serde() {
+        assert_eq!(serde_json::to_string(&SingleMode::Oneshot).unwrap(), r#""oneshot""#);
+        assert_eq!(serde_json::from_str::<ConsumeMode>(r#""on""#).unwrap(), ConsumeMode::On);
+    }
+}
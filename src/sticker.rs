@@ -1,19 +1,259 @@
-use crate::error::ParseError;
+//! The module defines sticker data structures
+//!
+//! Stickers are arbitrary name-value pairs MPD stores in its sticker database,
+//! attached to songs, playlists, tag values or filters (see [`StickerType`]).
+
+use crate::error::ProtoError;
+use crate::proto::ToArguments;
+use crate::search::Window;
+
+use std::fmt;
+use std::result::Result as StdResult;
 use std::str::FromStr;
 
+/// Type of objects stickers are attached to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StickerType {
+    /// song, identified by its URI
+    Song,
+    /// stored playlist, identified by its name (MPD 0.24+)
+    Playlist,
+    /// tag value, like `Album` or `Artist` (MPD 0.24+)
+    Tag(String),
+    /// filter expression (MPD 0.24+)
+    Filter,
+}
+
+impl fmt::Display for StickerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            StickerType::Song => "song",
+            StickerType::Playlist => "playlist",
+            StickerType::Tag(ref tag) => tag,
+            StickerType::Filter => "filter",
+        })
+    }
+}
+
+impl FromStr for StickerType {
+    type Err = ProtoError;
+    fn from_str(s: &str) -> Result<StickerType, ProtoError> {
+        match s {
+            "song" => Ok(StickerType::Song),
+            "playlist" => Ok(StickerType::Playlist),
+            "filter" => Ok(StickerType::Filter),
+            "" => Err(ProtoError::BadSticker),
+            tag => Ok(StickerType::Tag(tag.to_owned())),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for StickerType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for StickerType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Sticker
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Sticker {
+    /// sticker name
     pub name: String,
+    /// sticker value
     pub value: String,
 }
 
+impl Sticker {
+    /// Parse sticker value into some type, like a number
+    pub fn parse_value<T: FromStr>(&self) -> Result<T, ProtoError> {
+        self.value.parse().map_err(|_| ProtoError::BadSticker)
+    }
+}
+
+impl fmt::Display for Sticker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)
+    }
+}
+
 impl FromStr for Sticker {
-    type Err = ParseError;
-    fn from_str(s: &str) -> Result<Sticker, ParseError> {
-        let mut parts = s.splitn(2, '=');
-        match (parts.next(), parts.next()) {
-            (Some(name), Some(value)) => Ok(Sticker { name: name.to_owned(), value: value.to_owned() }),
-            _ => Err(ParseError::BadValue(s.to_owned())),
+    type Err = ProtoError;
+    fn from_str(s: &str) -> Result<Sticker, ProtoError> {
+        match s.split_once('=') {
+            Some((name, value)) => Ok(Sticker { name: name.to_owned(), value: value.to_owned() }),
+            None => Err(ProtoError::BadSticker),
         }
     }
 }
+
+/// Sticker of some object, as stored in sticker database
+///
+/// Used to export and import stickers, it can be (de)serialized with serde,
+/// e.g. into JSON or CSV, when `serde` feature is enabled.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StickerEntry {
+    /// object uri (or name for playlists, value for tags)
+    pub uri: String,
+    /// sticker name
+    pub name: String,
+    /// sticker value
+    pub value: String,
+}
+
+/// Sticker value comparison operator for `sticker find`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StickerOp {
+    /// `=`: values are equal as strings
+    Equals,
+    /// `<`: value is less than given one, compared as strings
+    Less,
+    /// `>`: value is greater than given one, compared as strings
+    Greater,
+    /// `eq`: values are equal as integers (MPD 0.24+)
+    EqualsInt,
+    /// `lt`: value is less than given one, compared as integers (MPD 0.24+)
+    LessInt,
+    /// `gt`: value is greater than given one, compared as integers (MPD 0.24+)
+    GreaterInt,
+}
+
+impl fmt::Display for StickerOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            StickerOp::Equals => "=",
+            StickerOp::Less => "<",
+            StickerOp::Greater => ">",
+            StickerOp::EqualsInt => "eq",
+            StickerOp::LessInt => "lt",
+            StickerOp::GreaterInt => "gt",
+        })
+    }
+}
+
+/// Sort order for `sticker find` results (MPD 0.24+)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StickerSort {
+    /// sort by object uri
+    Uri,
+    /// sort by sticker value as string
+    Value,
+    /// sort by sticker value as integer
+    ValueInt,
+}
+
+impl fmt::Display for StickerSort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            StickerSort::Uri => "uri",
+            StickerSort::Value => "value",
+            StickerSort::ValueInt => "value_int",
+        })
+    }
+}
+
+/// Additional conditions for `sticker find`: value comparison, sort order and window
+#[derive(Debug, Default)]
+pub struct StickerQuery {
+    filter: Option<(StickerOp, String)>,
+    sort: Option<(StickerSort, bool)>,
+    window: Option<(u32, u32)>,
+}
+
+impl StickerQuery {
+    /// Create query matching any sticker value
+    pub fn new() -> StickerQuery {
+        StickerQuery::default()
+    }
+
+    /// Match only stickers with values comparing to a given one with a given operator
+    pub fn filter<V: fmt::Display>(&mut self, op: StickerOp, value: V) -> &mut StickerQuery {
+        self.filter = Some((op, value.to_string()));
+        self
+    }
+
+    /// Sort results, in descending order if `descending` is set (MPD 0.24+)
+    pub fn sort(&mut self, sort: StickerSort, descending: bool) -> &mut StickerQuery {
+        self.sort = Some((sort, descending));
+        self
+    }
+
+    /// Return only a window (`start..end` range) of results (MPD 0.24+)
+    pub fn window<W: Into<Window>>(&mut self, window: W) -> &mut StickerQuery {
+        self.window = window.into().range();
+        self
+    }
+}
+
+impl ToArguments for &StickerQuery {
+    fn to_arguments<F, E>(&self, f: &mut F) -> StdResult<(), E>
+    where F: FnMut(&str) -> StdResult<(), E> {
+        if let Some((op, ref value)) = self.filter {
+            f(&op.to_string())?;
+            f(value)?;
+        }
+        if let Some((sort, descending)) = self.sort {
+            f("sort")?;
+            f(&format!("{}{}", if descending { "-" } else { "" }, sort))?;
+        }
+        Window::from(self.window).to_arguments(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sticker_type_round_trip() {
+        for typ in [StickerType::Song, StickerType::Playlist, StickerType::Tag("Album".into()), StickerType::Filter] {
+            assert_eq!(typ.to_string().parse::<StickerType>(), Ok(typ));
+        }
+    }
+
+    #[test]
+    fn sticker_parse() {
+        let sticker: Sticker = "rating=4=5".parse().unwrap();
+        assert_eq!(sticker, Sticker { name: "rating".into(), value: "4=5".into() });
+        assert_eq!(sticker.parse_value::<u8>(), Err(ProtoError::BadSticker));
+        assert_eq!("playCount=12".parse::<Sticker>().unwrap().parse_value::<u32>(), Ok(12));
+        assert_eq!("rating".parse::<Sticker>(), Err(ProtoError::BadSticker));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn sticker_entry_round_trip() {
+        let entries = vec![StickerEntry { uri: "a/b.flac".into(), name: "rating".into(), value: "8".into() }];
+        let json = serde_json::to_string(&entries).unwrap();
+        assert_eq!(json, r#"[{"uri":"a/b.flac","name":"rating","value":"8"}]"#);
+        assert_eq!(serde_json::from_str::<Vec<StickerEntry>>(&json).unwrap(), entries);
+    }
+
+    #[test]
+    fn sticker_query_format() {
+        let mut output = Vec::new();
+        let mut query = StickerQuery::new();
+        query.filter(StickerOp::GreaterInt, 3).sort(StickerSort::ValueInt, true).window((0, 10));
+        (&query)
+            .to_arguments::<_, ()>(&mut |arg| {
+                output.push(arg.to_owned());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(output, vec!["gt", "3", "sort", "-value_int", "window", "0:10"]);
+    }
+}
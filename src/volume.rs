@@ -0,0 +1,86 @@
+//! The module defines volume fading helper
+//!
+//! [`VolumeRamp`] changes volume gradually, in steps, and stops as soon as
+//! someone else changes volume, so it doesn't fight with the user or other clients.
+//!
+//! ```rust,no_run
+//! use mpd::{Client, VolumeRamp};
+//! use std::time::Duration;
+//!
+//! let mut conn = Client::connect("127.0.0.1:6600").unwrap();
+//! // Fade out over 5 seconds
+//! let completed = VolumeRamp::new(0, Duration::from_secs(5)).run(&mut conn).unwrap();
+//! ```
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::idle::{Idle, Subsystem};
+
+use std::io::{Read, Write};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Gradual volume change
+#[derive(Debug, Clone)]
+pub struct VolumeRamp {
+    from: Option<i8>,
+    to: i8,
+    duration: Duration,
+    steps: u32,
+}
+
+impl VolumeRamp {
+    /// Create ramp from current volume to a given level over a given time, in 20 steps
+    pub fn new(to: i8, duration: Duration) -> VolumeRamp {
+        VolumeRamp { from: None, to, duration, steps: 20 }
+    }
+
+    /// Start from a given volume level instead of the current one
+    pub fn from(&mut self, from: i8) -> &mut VolumeRamp {
+        self.from = Some(from);
+        self
+    }
+
+    /// Change volume in a given number of steps
+    pub fn steps(&mut self, steps: u32) -> &mut VolumeRamp {
+        self.steps = steps.max(1);
+        self
+    }
+
+    /// Run the ramp, blocking until it's done
+    ///
+    /// Between steps the connection waits for [`Mixer`](Subsystem::Mixer) events, and
+    /// if volume was changed by someone else, the ramp stops, leaving the volume alone.
+    ///
+    /// Returns `true` if the ramp was completed, `false` if it was interrupted
+    /// (or there is no mixer to change volume of).
+    pub fn run<S: Read + Write>(&self, client: &mut Client<S>) -> Result<bool> {
+        let from = match self.from {
+            Some(from) => from,
+            None => match client.status()?.volume {
+                Some(volume) => volume,
+                None => return Ok(false),
+            },
+        };
+        let interval = self.duration / self.steps;
+
+        for step in 1..=self.steps {
+            let level = (from as i32 + (self.to as i32 - from as i32) * step as i32 / self.steps as i32) as i8;
+            client.volume(level)?;
+            // Skip mixer event caused by our own change
+            client.idle(&[Subsystem::Mixer])?.cancel()?;
+            if step == self.steps {
+                break;
+            }
+            // Hardware mixers may round the level, so compare with what was actually set
+            let set = client.status()?.volume;
+
+            let guard = client.idle(&[Subsystem::Mixer])?;
+            sleep(interval);
+            if guard.cancel()?.contains(&Subsystem::Mixer) && client.status()?.volume != set {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
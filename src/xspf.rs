@@ -0,0 +1,287 @@
+//! The module defines XSPF playlist reader and writer (requires `xspf` feature)
+//!
+//! XSPF playlists (usually coming from web services) identify tracks by their tags
+//! rather than by paths, so on import each track is resolved to a song in MPD database
+//! (see [`Xspf::resolve()`]), and tracks which can't be found are reported back.
+
+use crate::client::Client;
+use crate::error::{Error, ParseError, Result};
+use crate::playlist_file::percent_decode;
+use crate::search::{Operation, Query, Term};
+use crate::song::{Id, Song};
+
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use std::io::{BufRead, Read, Write};
+use std::time::Duration;
+
+/// XSPF track
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XspfTrack {
+    /// track location (URI), song paths are written percent-encoded
+    pub location: Option<String>,
+    /// track title
+    pub title: Option<String>,
+    /// track artist
+    pub creator: Option<String>,
+    /// album name
+    pub album: Option<String>,
+    /// track duration
+    pub duration: Option<Duration>,
+}
+
+impl<'a> From<&'a Song> for XspfTrack {
+    fn from(song: &'a Song) -> XspfTrack {
+        XspfTrack {
+            location: Some(if song.file.contains("://") { song.file.clone() } else { percent_encode(song.file_bytes()) }),
+            title: song.title.clone(),
+            creator: song.artist.clone(),
+            album: song.tags.iter().find(|(tag, _)| tag == "Album").map(|(_, album)| album.clone()),
+            duration: song.duration,
+        }
+    }
+}
+
+/// XSPF playlist
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Xspf {
+    /// playlist title
+    pub title: Option<String>,
+    /// playlist tracks, in order
+    pub tracks: Vec<XspfTrack>,
+}
+
+/// Result of resolving XSPF tracks to songs in MPD database
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XspfResolution {
+    /// resolved songs, in playlist order
+    pub songs: Vec<Song>,
+    /// tracks which were not found in the database, with their indices in the playlist
+    pub unresolved: Vec<(usize, XspfTrack)>,
+}
+
+impl XspfResolution {
+    /// Append resolved songs to the queue, returns ids of added songs
+    pub fn push_to_queue<S: Read + Write>(&self, client: &mut Client<S>) -> Result<Vec<Id>> {
+        self.songs.iter().map(|song| client.push(song)).collect()
+    }
+
+    /// Append resolved songs to a stored playlist, creating it if needed
+    pub fn push_to_playlist<S: Read + Write>(&self, client: &mut Client<S>, name: &str) -> Result<()> {
+        self.songs.iter().try_for_each(|song| client.pl_push(name, song))
+    }
+}
+
+impl Xspf {
+    /// Build playlist from songs, e.g. from [`Client::queue()`] or [`Client::playlist()`]
+    pub fn from_songs(title: Option<String>, songs: &[Song]) -> Xspf {
+        Xspf { title, tracks: songs.iter().map(XspfTrack::from).collect() }
+    }
+
+    /// Parse XSPF document
+    pub fn parse<R: BufRead>(reader: R) -> Result<Xspf> {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+
+        let mut xspf = Xspf::default();
+        let mut path: Vec<Vec<u8>> = Vec::new();
+        let mut buf = Vec::new();
+        loop {
+            let text = match reader.read_event_into(&mut buf).map_err(bad_xml)? {
+                Event::Start(tag) => {
+                    if tag.local_name().as_ref() == b"track" {
+                        xspf.tracks.push(XspfTrack::default());
+                    }
+                    path.push(tag.local_name().as_ref().to_vec());
+                    None
+                }
+                Event::End(_) => {
+                    path.pop();
+                    None
+                }
+                Event::Text(text) => Some(text.unescape().map_err(bad_xml)?.into_owned()),
+                Event::CData(data) => Some(String::from_utf8_lossy(&data).into_owned()),
+                Event::Eof => break,
+                _ => None,
+            };
+            buf.clear();
+
+            let text = match text {
+                Some(text) => text,
+                None => continue,
+            };
+            let names: Vec<&[u8]> = path.iter().map(|name| &name[..]).collect();
+            match names[..] {
+                [b"playlist", b"title"] => xspf.title = Some(text),
+                [b"playlist", b"trackList", b"track", field] => {
+                    let track = match xspf.tracks.last_mut() {
+                        Some(track) => track,
+                        None => continue,
+                    };
+                    match field {
+                        b"location" => track.location = Some(text),
+                        b"title" => track.title = Some(text),
+                        b"creator" => track.creator = Some(text),
+                        b"album" => track.album = Some(text),
+                        b"duration" => track.duration = text.trim().parse().ok().map(Duration::from_millis),
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(xspf)
+    }
+
+    /// Write XSPF document
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(writer, r#"<playlist version="1" xmlns="http://xspf.org/ns/0/">"#)?;
+        if let Some(ref title) = self.title {
+            writeln!(writer, "  <title>{}</title>", escape(title))?;
+        }
+        writeln!(writer, "  <trackList>")?;
+        for track in &self.tracks {
+            writeln!(writer, "    <track>")?;
+            let fields = [("location", &track.location), ("title", &track.title), ("creator", &track.creator), ("album", &track.album)];
+            for (name, value) in fields {
+                if let Some(value) = value {
+                    writeln!(writer, "      <{0}>{1}</{0}>", name, escape(value))?;
+                }
+            }
+            if let Some(duration) = track.duration {
+                writeln!(writer, "      <duration>{}</duration>", duration.as_millis())?;
+            }
+            writeln!(writer, "    </track>")?;
+        }
+        writeln!(writer, "  </trackList>")?;
+        writeln!(writer, "</playlist>")?;
+        Ok(())
+    }
+
+    /// Resolve tracks to songs in MPD database
+    ///
+    /// Tracks with a title are looked up by title, creator (artist) and album with exact
+    /// tag matches first (`find`), falling back to case insensitive search (`search`).
+    /// Tracks without a title are looked up by their location as a song URI.
+    /// Tracks server failed to look up are reported as unresolved too.
+    pub fn resolve<S: Read + Write>(&self, client: &mut Client<S>) -> Result<XspfResolution> {
+        let mut resolution = XspfResolution::default();
+        for (idx, track) in self.tracks.iter().enumerate() {
+            match resolve_track(client, track) {
+                Ok(Some(song)) => resolution.songs.push(song),
+                // Server may refuse queries built from unusual tags, don't let one track fail the import
+                Ok(None) | Err(Error::Server(_)) => resolution.unresolved.push((idx, track.clone())),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(resolution)
+    }
+}
+
+fn resolve_track<S: Read + Write>(client: &mut Client<S>, track: &XspfTrack) -> Result<Option<Song>> {
+    let tags = [("title", &track.title), ("artist", &track.creator), ("album", &track.album)];
+
+    if track.title.is_none() {
+        let location = match track.location {
+            Some(ref location) => location,
+            None => return Ok(None),
+        };
+        let mut query = Query::new();
+        query.and(Term::File, location_path(location));
+        return Ok(client.find(&query, None)?.into_iter().next());
+    }
+
+    let mut query = Query::new();
+    for (tag, value) in tags {
+        if let Some(value) = value {
+            query.and(Term::Tag(tag.into()), value.as_str());
+        }
+    }
+    if let Some(song) = client.find(&query, None)?.into_iter().next() {
+        return Ok(Some(song));
+    }
+
+    let mut query = Query::new();
+    for (tag, value) in tags {
+        if let Some(value) = value {
+            query.and_with_op(Term::Tag(tag.into()), Operation::Contains, value.as_str());
+        }
+    }
+    Ok(client.search(&query, None)?.into_iter().next())
+}
+
+/// Turn track location into song path: decode `file://` URLs and relative URIs, leave other URLs as is
+fn location_path(location: &str) -> String {
+    match location.strip_prefix("file://") {
+        Some(path) => percent_decode(path),
+        None if !location.contains("://") => percent_decode(location),
+        None => location.to_owned(),
+    }
+}
+
+/// Escape bytes which can't appear in URI path as is, keeping `/` separators
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => result.push(byte as char),
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+fn bad_xml<E: std::fmt::Display>(e: E) -> Error {
+    Error::Parse(ParseError::BadValue(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const XSPF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>Mix &amp; Match</title>
+  <trackList>
+    <track>
+      <title>Salad Days</title>
+      <creator>Mac DeMarco</creator>
+      <album>Salad Days</album>
+      <duration>155000</duration>
+    </track>
+    <track><location>http://radio/stream</location></track>
+  </trackList>
+</playlist>
+"#;
+
+    #[test]
+    fn parse_and_write() {
+        let xspf = Xspf::parse(XSPF.as_bytes()).unwrap();
+        assert_eq!(xspf.title.as_deref(), Some("Mix & Match"));
+        assert_eq!(xspf.tracks.len(), 2);
+        assert_eq!(xspf.tracks[0].creator.as_deref(), Some("Mac DeMarco"));
+        assert_eq!(xspf.tracks[0].duration, Some(Duration::from_secs(155)));
+        assert_eq!(xspf.tracks[1].location.as_deref(), Some("http://radio/stream"));
+
+        let mut output = Vec::new();
+        xspf.write(&mut output).unwrap();
+        assert_eq!(Xspf::parse(&output[..]).unwrap(), xspf);
+    }
+
+    #[test]
+    fn locations() {
+        let song = Song { file: "Mac DeMarco/Salad Days #1.flac".into(), ..Song::default() };
+        let location = XspfTrack::from(&song).location.unwrap();
+        assert_eq!(location, "Mac%20DeMarco/Salad%20Days%20%231.flac");
+        assert_eq!(location_path(&location), song.file);
+
+        let song = Song { file: "caf\u{fffd}.flac".into(), raw_file: Some(b"caf\xe9.flac".to_vec()), ..Song::default() };
+        assert_eq!(XspfTrack::from(&song).location.as_deref(), Some("caf%E9.flac"));
+
+        assert_eq!(location_path("file:///music/a%20b.flac"), "/music/a b.flac");
+        assert_eq!(location_path("http://radio/stream%20one"), "http://radio/stream%20one");
+    }
+}
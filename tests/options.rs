@@ -2,7 +2,7 @@ extern crate mpd;
 
 mod helpers;
 use helpers::connect;
-use mpd::{Idle, Song, State, Subsystem};
+use mpd::{ConsumeMode, Idle, SingleMode, Song, State, Subsystem};
 use std::time::Duration;
 
 #[test]
@@ -57,21 +57,48 @@ macro_rules! test_option {
     };
 }
 
-test_option!(consume, true, false);
-test_option!(single, true, false);
+test_option!(consume, ConsumeMode::On, ConsumeMode::Off);
+test_option!(single, SingleMode::Oneshot, SingleMode::Off);
 test_option!(random, true, false);
 test_option!(repeat, true, false);
 // test_option!(mixrampdb, 1.0f32, 0.0f32);
 // test_option!(mixrampdelay, 1 => Some(Duration::from_secs(1)), 0 => None);
 
+#[test]
+fn single_consume_bool() {
+    let mut mpd = connect();
+    mpd.single(true).unwrap();
+    mpd.consume(true).unwrap();
+    let status = mpd.status().unwrap();
+    assert_eq!((status.single, status.consume), (SingleMode::On, ConsumeMode::On));
+    mpd.single(false).unwrap();
+    mpd.consume(false).unwrap();
+}
+
 #[test]
 fn volume() {
     let mut mpd = connect();
-    if mpd.status().unwrap().volume >= 0 {
+    if mpd.status().unwrap().volume.is_some() {
         mpd.volume(100).unwrap();
-        assert_eq!(mpd.status().unwrap().volume, 100);
+        assert_eq!(mpd.status().unwrap().volume, Some(100));
         mpd.volume(0).unwrap();
-        assert_eq!(mpd.status().unwrap().volume, 0);
+        assert_eq!(mpd.status().unwrap().volume, Some(0));
+    }
+}
+
+#[test]
+fn volume_change() {
+    let mut mpd = connect();
+    if mpd.status().unwrap().volume.is_some() {
+        mpd.volume(50).unwrap();
+        mpd.volume_change(10).unwrap();
+        assert_eq!(mpd.getvol().unwrap(), Some(60));
+        mpd.volume_change(-20).unwrap();
+        assert_eq!(mpd.getvol().unwrap(), Some(40));
+
+        let completed = mpd::VolumeRamp::new(0, Duration::from_millis(100)).steps(4).run(&mut mpd).unwrap();
+        assert!(completed);
+        assert_eq!(mpd.getvol().unwrap(), Some(0));
     }
 }
 
@@ -83,3 +110,12 @@ fn crossfade() {
     mpd.crossfade(0).unwrap();
     assert_eq!(mpd.status().unwrap().crossfade, if mpd.version >= mpd::Version(0, 19, 0) { None } else { Some(Duration::from_secs(0)) });
 }
+
+#[test]
+fn replaygain() {
+    let mut mpd = connect();
+    mpd.replaygain(mpd::ReplayGain::Album).unwrap();
+    assert_eq!(mpd.replaygain_status().unwrap(), mpd::ReplayGain::Album);
+    mpd.replaygain(mpd::ReplayGain::Off).unwrap();
+    assert_eq!(mpd.replaygain_status().unwrap(), mpd::ReplayGain::Off);
+}
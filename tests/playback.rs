@@ -7,3 +7,22 @@ fn playback() {
     let mut mpd = helpers::connect();
     mpd.play().unwrap();
 }
+
+#[test]
+fn seek_relative() {
+    use mpd::SeekTarget;
+    use std::time::Duration;
+
+    let mut mpd = helpers::connect();
+    mpd.clear().unwrap();
+    mpd.push(mpd::Song { file: "silence.flac".into(), ..Default::default() }).unwrap();
+    mpd.play().unwrap();
+    mpd.pause(true).unwrap();
+
+    mpd.rewind(SeekTarget::Absolute(Duration::from_millis(100))).unwrap();
+    mpd.rewind(SeekTarget::Forward(Duration::from_millis(200))).unwrap();
+    let elapsed = mpd.status().unwrap().elapsed.unwrap();
+    assert!(elapsed >= Duration::from_millis(290) && elapsed <= Duration::from_millis(310), "{:?}", elapsed);
+    mpd.rewind(SeekTarget::Backward(Duration::from_millis(300))).unwrap();
+    assert_eq!(SeekTarget::Forward(Duration::from_millis(1500)).to_string(), "+1.5");
+}
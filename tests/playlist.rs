@@ -13,3 +13,65 @@ fn playlists() {
         println!("{}: {:?}", pl.name, mpd.playlist(&pl.name).unwrap());
     }
 }
+
+#[test]
+fn playlist_editing() {
+    let mut mpd = connect();
+    let song = mpd::Song { file: "silence.flac".into(), ..Default::default() };
+    let _ = mpd.pl_remove("editing");
+    for _ in 0..4 {
+        mpd.pl_push("editing", &song).unwrap();
+    }
+    mpd.pl_insert("editing", &song, 0).unwrap();
+    assert_eq!(mpd.playlist_uris("editing").unwrap(), vec!["silence.flac"; 5]);
+
+    mpd.pl_delete_range("editing", 1..3).unwrap();
+    mpd.pl_shift_range("editing", 0..2, 1).unwrap();
+    assert_eq!(mpd.pl_length("editing").unwrap().0, 3);
+    assert_eq!(mpd.playlist_range("editing", 1..3).unwrap().len(), 2);
+
+    let mut query = mpd::Query::new();
+    query.and(mpd::Term::File, "silence.flac");
+    assert_eq!(mpd.pl_search("editing", &query, ..).unwrap().len(), 3);
+    assert_eq!(mpd.pl_search("editing", &query, 1..3).unwrap().len(), 2);
+    mpd.pl_remove("editing").unwrap();
+}
+
+#[test]
+fn playlist_file_import_export() {
+    use mpd::{PlaylistFile, PlaylistFormat};
+
+    let mut mpd = connect();
+    mpd.clear().unwrap();
+    mpd.push(mpd::Song { file: "silence.flac".into(), ..Default::default() }).unwrap();
+
+    let mut output = Vec::new();
+    PlaylistFile::from_queue(&mut mpd).unwrap().absolute_in("/music").write(PlaylistFormat::M3u, &mut output).unwrap();
+
+    let mut file = PlaylistFile::parse(PlaylistFormat::M3u, &output[..]).unwrap();
+    assert_eq!(file.entries[0].uri, "/music/silence.flac");
+    let _ = mpd.pl_remove("imported");
+    file.relative_to("/music").push_to_playlist(&mut mpd, "imported").unwrap();
+    assert_eq!(mpd.playlist_uris("imported").unwrap(), vec!["silence.flac"]);
+    assert_eq!(file.push_to_queue(&mut mpd).unwrap().len(), 1);
+    mpd.pl_remove("imported").unwrap();
+}
+
+#[cfg(feature = "xspf")]
+#[test]
+fn xspf_resolve() {
+    use mpd::{Xspf, XspfTrack};
+
+    let mut mpd = connect();
+    let xspf = Xspf {
+        title: None,
+        tracks: vec![
+            XspfTrack { location: Some("silence.flac".into()), ..Default::default() },
+            XspfTrack { title: Some("No Such Song".into()), creator: Some("Nobody".into()), ..Default::default() },
+        ],
+    };
+    let resolution = xspf.resolve(&mut mpd).unwrap();
+    assert_eq!(resolution.songs.len(), 1);
+    assert_eq!(resolution.songs[0].file, "silence.flac");
+    assert_eq!(resolution.unresolved, vec![(1, xspf.tracks[1].clone())]);
+}
@@ -13,3 +13,26 @@ fn search() {
     println!("{:?}", songs);
     assert!(songs.is_ok());
 }
+
+#[test]
+fn queue_search() {
+    let mut mpd = connect();
+    mpd.clear().unwrap();
+    let id = mpd.push(mpd::Song { file: "silence.flac".into(), ..Default::default() }).unwrap();
+    mpd.priority(id, 50).unwrap();
+
+    let mut query = Query::new();
+    let songs = mpd.queue_find(query.and(mpd::Term::File, "silence.flac")).unwrap();
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].place.unwrap().id, id);
+
+    let mut query = Query::new();
+    assert_eq!(mpd.queue_search(query.and_with_op(mpd::Term::File, mpd::search::Operation::Contains, "SILENCE")).unwrap().len(), 1);
+    assert_eq!(mpd.queue_find(Query::new().priority_at_least(50)).unwrap().len(), 1);
+    assert!(mpd.queue_find(Query::new().priority_at_least(51)).unwrap().is_empty());
+
+    let mut query = Query::new();
+    assert_eq!(mpd.queue_find(query.and(mpd::Term::File, "silence.flac").priority_at_least(50)).unwrap().len(), 1);
+    let mut query = Query::new();
+    assert!(mpd.queue_find(query.and(mpd::Term::File, "silence.flac").priority_at_least(51)).unwrap().is_empty());
+}
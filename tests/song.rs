@@ -1,6 +1,8 @@
 extern crate mpd;
+extern crate tempfile;
 
 mod helpers;
+use std::io::{self, Read, Write};
 use std::time::Duration;
 
 use helpers::connect;
@@ -40,3 +42,203 @@ fn rescan_update() {
     println!("update: {:?}", mpd.update());
     println!("rescan: {:?}", mpd.rescan());
 }
+
+#[test]
+fn cover_art() {
+    let mut mpd = connect();
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    assert_eq!(mpd.readpicture(&song).unwrap(), None);
+    assert_eq!(mpd.albumart(&song).unwrap(), None);
+    assert_eq!(mpd.cover_art(&song).unwrap(), None);
+}
+
+#[test]
+fn albumart_to_writer() {
+    let mut mpd = connect();
+    if mpd.version >= mpd::Version(0, 22, 4) {
+        mpd.binarylimit(64 * 1024).unwrap();
+    }
+
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    let mut buf = Vec::new();
+    assert_eq!(mpd.albumart_to_writer(&song, &mut buf).unwrap(), None);
+    assert_eq!(mpd.readpicture_to_writer(&song, &mut buf).unwrap(), None);
+    assert!(buf.is_empty());
+}
+
+/// Stream replaying a canned server response, ignoring commands
+struct Script(io::Cursor<&'static [u8]>);
+
+impl Read for Script {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Script {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writer which refuses all data
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "broken"))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn albumart_to_failing_writer() {
+    let response = b"OK MPD 0.23.0\nsize: 6\ntype: image/png\nbinary: 6\nabcdef\nOK\nOK\n";
+    let mut mpd = mpd::Client::new(Script(io::Cursor::new(&response[..]))).unwrap();
+    let song = Song { file: "cover.flac".into(), ..Default::default() };
+
+    match mpd.albumart_to_writer(&song, &mut Broken) {
+        Err(mpd::error::Error::Io(e)) => assert_eq!(e.to_string(), "broken"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    // The rest of the response was consumed, so the next command reads its own reply
+    mpd.ping().unwrap();
+}
+
+#[test]
+fn art_cache() {
+    let mut mpd = connect();
+    let dir = tempfile::TempDir::with_prefix("mpd-art-cache").unwrap();
+    let mut cache = mpd::ArtCache::open(dir.path(), 1024 * 1024).unwrap();
+
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    assert_eq!(cache.get(&mut mpd, &song).unwrap(), None);
+    // Missing art is cached as well
+    assert_eq!(cache.get(&mut mpd, &song).unwrap(), None);
+    assert_eq!(cache.size(), 0);
+
+    cache.update(&[mpd::Subsystem::Database]);
+    assert_eq!(cache.get(&mut mpd, &song).unwrap(), None);
+}
+
+#[test]
+fn art_cache_broken_entries() {
+    let dir = tempfile::TempDir::with_prefix("mpd-art-cache").unwrap();
+    std::fs::write(dir.path().join("0000000000000001.bin"), b"image").unwrap();
+    std::fs::write(dir.path().join("0000000000000002.bin"), b"image").unwrap();
+    std::fs::write(dir.path().join("0000000000000002.meta"), b"truncated").unwrap();
+    std::fs::write(dir.path().join("0000000000000003.meta"), b"\n\n\n0\n").unwrap();
+    std::fs::write(dir.path().join("README"), b"unrelated").unwrap();
+
+    let cache = mpd::ArtCache::open(dir.path(), 1024).unwrap();
+    assert_eq!(cache.size(), 0);
+    let mut names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().map(|item| item.unwrap().file_name()).collect();
+    names.sort();
+    assert_eq!(names, vec!["README"]);
+}
+
+#[test]
+fn queue_mirror() {
+    let mut mpd = connect();
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    mpd.clear().unwrap();
+
+    let mut mirror = mpd::QueueMirror::new();
+    assert!(mirror.sync(&mut mpd).unwrap());
+    assert!(mirror.songs().is_empty());
+
+    mpd.push(&song).unwrap();
+    mpd.push(&song).unwrap();
+    mpd.push(&song).unwrap();
+    assert!(mirror.update(&mut mpd, &[mpd::Subsystem::Queue]).unwrap());
+    assert_eq!(mirror.songs(), &mpd.queue().unwrap()[..]);
+
+    mpd.swap(0, 2).unwrap();
+    mpd.delete(1).unwrap();
+    assert!(mirror.sync(&mut mpd).unwrap());
+    assert_eq!(mirror.songs(), &mpd.queue().unwrap()[..]);
+    assert!(!mirror.sync(&mut mpd).unwrap());
+}
+
+#[test]
+fn queue_changes() {
+    let mut mpd = connect();
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    mpd.clear().unwrap();
+    let one = mpd.push(&song).unwrap();
+    let two = mpd.push(&song).unwrap();
+    let three = mpd.push(&song).unwrap();
+
+    let version = mpd.status().unwrap().queue_version;
+    mpd.swap(0, 2).unwrap();
+    assert_eq!(mpd.changes_posid(version).unwrap(), vec![(0, three), (2, one)]);
+
+    let songs = mpd.queue_songs(&[0..1], &[two]).unwrap();
+    let ids: Vec<_> = songs.iter().map(|s| s.place.unwrap().id).collect();
+    assert_eq!(ids, vec![three, two]);
+    assert!(mpd.queue_songs(&[], &[]).unwrap().is_empty());
+}
+
+#[test]
+fn reconcile_queue() {
+    let mut mpd = connect();
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    mpd.clear().unwrap();
+    let first = mpd.push(&song).unwrap();
+    mpd.push(&song).unwrap();
+    mpd.push(&song).unwrap();
+
+    let ids = mpd.reconcile_queue(&["silence.flac", "silence.flac"]).unwrap();
+    assert_eq!(ids[0], first);
+    let queue: Vec<_> = mpd.queue().unwrap().into_iter().map(|s| s.place.unwrap().id).collect();
+    assert_eq!(queue, ids);
+
+    let ids = mpd.reconcile_queue(&["silence.flac"; 4]).unwrap();
+    let queue: Vec<_> = mpd.queue().unwrap().into_iter().map(|s| s.place.unwrap().id).collect();
+    assert_eq!(queue, ids);
+    assert!(mpd.reconcile_queue(&[]).unwrap().is_empty());
+    assert!(mpd.queue().unwrap().is_empty());
+}
+
+#[test]
+fn relative_positions() {
+    use mpd::Position;
+
+    let mut mpd = connect();
+    let song = Song { file: "silence.flac".into(), ..Default::default() };
+    mpd.clear().unwrap();
+    mpd.push(&song).unwrap();
+    let last = mpd.insert(&song, 1).unwrap();
+    mpd.shift(1, 0).unwrap();
+    assert_eq!(mpd.queue().unwrap()[0].place.unwrap().id.0 as usize, last);
+
+    mpd.switch(0).unwrap();
+    mpd.pause(true).unwrap();
+    let next = mpd.insert(&song, Position::AfterCurrent(0)).unwrap();
+    assert_eq!(mpd.queue().unwrap()[1].place.unwrap().id.0 as usize, next);
+    mpd.add_at(&song, Position::BeforeCurrent(0)).unwrap();
+    assert_eq!(mpd.queue().unwrap().len(), 4);
+    assert_eq!("+2".parse::<Position>().unwrap(), Position::AfterCurrent(2));
+    assert_eq!(Position::BeforeCurrent(1).to_string(), "-1");
+}
+
+#[test]
+fn cue_sheet() {
+    let mut mpd = connect();
+    mpd.clear().unwrap();
+    let sheet: mpd::CueSheet = "FILE \"silence.flac\" WAVE\n  TRACK 01 AUDIO\n    TITLE \"First\"\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n    INDEX 01 00:00:20\n"
+        .parse()
+        .unwrap();
+    let queued = sheet.push_to_queue(&mut mpd, "").unwrap();
+    assert_eq!(queued.ids.len(), 2);
+    // Local songs can't be tagged, the track with a title is reported
+    assert_eq!(queued.untagged, &queued.ids[..1]);
+
+    let queue = mpd.queue().unwrap();
+    assert_eq!(queue[0].range, Some(mpd::song::Range(Duration::ZERO, Some(Duration::from_millis(266)))));
+}
@@ -2,6 +2,8 @@ extern crate mpd;
 
 mod helpers;
 use helpers::connect;
+use mpd::error::{Error, ProtoError};
+use mpd::{Bookmarks, Library, Query, Song, Sticker, StickerEntry, StickerOp, StickerQuery, StickerSort, StickerType, Subsystem};
 
 #[test]
 /// Creating a sticker and then getting that sticker returns the value that was set.
@@ -12,6 +14,119 @@ fn set_sticker() {
 
     mpd.set_sticker("song", "silence.flac", "test_sticker", VALUE).unwrap();
 
-    let sticker = mpd.sticker("song", "silence.flac", "test_sticker").unwrap();
+    let sticker: String = mpd.sticker("song", "silence.flac", "test_sticker").unwrap();
     assert_eq!(sticker, VALUE);
 }
+
+#[test]
+/// Sticker values are converted to and from typed values.
+fn typed_stickers() {
+    let mut mpd = connect();
+
+    mpd.set_sticker(StickerType::Song, "silence.flac", "rating", 4u8).unwrap();
+    assert_eq!(mpd.sticker::<_, u8>(StickerType::Song, "silence.flac", "rating").unwrap(), 4);
+    assert_eq!(mpd.stickers(StickerType::Song, "silence.flac").unwrap(), vec![Sticker { name: "rating".into(), value: "4".into() }]);
+    assert_eq!(mpd.find_sticker::<_, u8>(StickerType::Song, "", "rating").unwrap(), vec![("silence.flac".into(), 4)]);
+
+    mpd.set_sticker(StickerType::Song, "silence.flac", "rating", "bad").unwrap();
+    match mpd.sticker::<_, u8>(StickerType::Song, "silence.flac", "rating") {
+        Err(Error::Proto(ProtoError::BadSticker)) => (),
+        other => panic!("expected bad sticker error, got {:?}", other),
+    }
+}
+
+#[test]
+/// Counters are incremented on server side, and stickers can be searched with comparisons.
+fn sticker_counters() {
+    let mut mpd = connect();
+    if mpd.version < mpd::Version(0, 24, 0) {
+        return;
+    }
+
+    mpd.inc_sticker(StickerType::Song, "silence.flac", "playCount", 2).unwrap();
+    mpd.dec_sticker(StickerType::Song, "silence.flac", "playCount", 1).unwrap();
+    assert_eq!(mpd.sticker::<_, u32>(StickerType::Song, "silence.flac", "playCount").unwrap(), 1);
+
+    let mut query = StickerQuery::new();
+    query.filter(StickerOp::GreaterInt, 0).sort(StickerSort::ValueInt, true).window((0, 10));
+    assert_eq!(mpd.find_sticker_by::<_, u32>(StickerType::Song, "", "playCount", &query).unwrap(), vec![("silence.flac".into(), 1)]);
+
+    assert!(mpd.stickernames().unwrap().contains(&"playCount".to_owned()));
+    assert!(mpd.stickertypes().unwrap().contains(&StickerType::Song));
+    assert!(mpd.stickernamestypes(Some(StickerType::Song)).unwrap().contains(&("playCount".into(), StickerType::Song)));
+}
+
+#[test]
+/// Ratings and play counts are stored in stickers shared with other clients.
+fn library() {
+    let mut mpd = connect();
+    let song = Song { file: "silence.flac".into(), ..Song::default() };
+    let mut library = Library::new(&mut mpd);
+
+    assert_eq!(library.rating(&song).unwrap(), None);
+    library.set_rating(&song, 8).unwrap();
+    assert_eq!(library.rating(&song).unwrap(), Some(8));
+    assert!(library.set_rating(&song, 11).is_err());
+
+    // Stickers can't address songs with non-UTF-8 names
+    let lossy = Song { file: "caf\u{fffd}.flac".into(), raw_file: Some(b"caf\xe9.flac".to_vec()), ..Song::default() };
+    assert!(library.rating(&lossy).is_err());
+    assert!(library.set_rating(&lossy, 5).is_err());
+
+    assert_eq!(library.play_count(&song).unwrap(), 0);
+    assert_eq!(library.last_played(&song).unwrap(), None);
+    library.mark_played(&song).unwrap();
+    library.increment_play_count(&song).unwrap();
+    assert_eq!(library.play_count(&song).unwrap(), 2);
+    assert!(library.last_played(&song).unwrap().is_some());
+
+    let top = library.top_rated(&Query::new(), 10).unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!((&*top[0].0.file, top[0].1), ("silence.flac", 8));
+
+    // Malformed ratings are skipped instead of failing the whole call
+    mpd.set_sticker(StickerType::Song, "silence.flac", "rating", "bad").unwrap();
+    let mut library = Library::new(&mut mpd);
+    assert!(library.top_rated(&Query::new(), 10).unwrap().is_empty());
+
+    library.clear_rating(&song).unwrap();
+    assert_eq!(library.rating(&song).unwrap(), None);
+}
+
+#[test]
+/// Exported stickers can be imported back, with paths remapped.
+fn export_import_stickers() {
+    let mut mpd = connect();
+    mpd.set_sticker(StickerType::Song, "silence.flac", "rating", 6).unwrap();
+
+    let exported: Vec<StickerEntry> = mpd.export_stickers(StickerType::Song, "").unwrap().collect();
+    assert_eq!(exported, vec![StickerEntry { uri: "silence.flac".into(), name: "rating".into(), value: "6".into() }]);
+
+    mpd.clear_stickers(StickerType::Song, "silence.flac").unwrap();
+    let missing = mpd.import_stickers(StickerType::Song, exported.clone(), |uri| Some(format!("old/{}", uri))).unwrap();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].uri, "old/silence.flac");
+
+    let missing = mpd.import_stickers(StickerType::Song, exported, |uri| Some(uri.to_owned())).unwrap();
+    assert!(missing.is_empty());
+    assert_eq!(mpd.sticker::<_, u8>(StickerType::Song, "silence.flac", "rating").unwrap(), 6);
+}
+
+#[test]
+/// Playback position is saved only for long enough files.
+fn bookmarks() {
+    let mut mpd = connect();
+    let mut bookmarks = Bookmarks::new();
+
+    mpd.push(Song { file: "silence.flac".into(), ..Song::default() }).unwrap();
+    mpd.play().unwrap();
+    bookmarks.update(&mut mpd, &[Subsystem::Player]).unwrap();
+    mpd.stop().unwrap();
+    bookmarks.update(&mut mpd, &[Subsystem::Player]).unwrap();
+    assert_eq!(bookmarks.bookmark(&mut mpd, "silence.flac").unwrap(), None);
+
+    mpd.set_sticker(StickerType::Song, "silence.flac", mpd::bookmarks::ELAPSED, 1).unwrap();
+    assert_eq!(bookmarks.bookmark(&mut mpd, "silence.flac").unwrap(), Some(std::time::Duration::from_secs(1)));
+    bookmarks.clear(&mut mpd, "silence.flac").unwrap();
+    assert_eq!(bookmarks.bookmark(&mut mpd, "silence.flac").unwrap(), None);
+}